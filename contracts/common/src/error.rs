@@ -28,6 +28,26 @@ pub enum QuipayError {
     StreamClosed = 1018,
     NotEmployer = 1019,
     StreamNotClosed = 1020,
+    AlreadyAuthorized = 1021,
+    NotAuthorized = 1022,
+    OutstandingLiability = 1023,
+    StrategyNotSet = 1024,
+    StrategyHasFunds = 1025,
+    StrategyCapExceeded = 1026,
+    ReserveRequirement = 1027,
+    ApprovalRequired = 1028,
+    ApproversNotSet = 1029,
+    NotApprover = 1030,
+    AlreadyApproved = 1031,
+    InsufficientApprovals = 1032,
+    InvalidThreshold = 1033,
+    ProposalNotFound = 1034,
+    ProposalExpired = 1035,
+    ProposalClosed = 1036,
+    EmptyMetadataUri = 1037,
+    MetadataUriTooLong = 1038,
+    InvalidSchemaVersion = 1039,
+    MetadataSchemeMismatch = 1040,
     Custom = 1999,
 }
 
@@ -1,6 +1,14 @@
 #![no_std]
 use quipay_common::{require, QuipayError};
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Symbol, Vec};
+use soroban_sdk::{
+    contract, contractimpl, contracttype, token, vec, Address, Env, IntoVal, Symbol, Vec,
+};
+
+mod oracle;
+mod swap;
+pub use oracle::{Asset, OracleConfig, PriceData, PriceOracle, PriceOracleClient};
+use oracle::PriceAnchor;
+pub use swap::{SwapAdapter, SwapAdapterClient, SwapConfig};
 
 #[contracttype]
 #[derive(Clone)]
@@ -10,6 +18,11 @@ pub enum DataKey {
     NextStreamId,
     RetentionSecs,
     Vault,
+    Registry,
+    SwapConfig,
+    NextBasketId,
+    /// Price feed per reference currency, for fiat-denominated streams.
+    PriceOracle(Symbol),
 }
 
 #[contracttype]
@@ -25,8 +38,40 @@ pub enum StreamStatus {
 #[derive(Clone)]
 pub enum StreamKey {
     Stream(u64),
+    /// Legacy unpaged employer index, moved into `IndexEntry` slots on first access.
     EmployerStreams(Address),
+    /// Legacy unpaged worker index, moved into `IndexEntry` slots on first access.
     WorkerStreams(Address),
+    RequiredAttestations(Address),
+    StreamTeam(u64),
+    /// Fee cap per withdrawal a worker allows a delegate to take, keyed by (worker, delegate).
+    WithdrawDelegate(Address, Address),
+    /// Number of slots ever allocated in an index; cleaned-up streams leave empty slots.
+    IndexSlots(StreamIndex),
+    IndexEntry(StreamIndex, u32),
+    IndexSlot(StreamIndex, u64),
+    EmployerStats(Address, Address),
+    WorkerStats(Address),
+    /// Set when an employer must co-sign transfers of its streams.
+    TransferConsent(Address),
+    AdvancePolicy(Address),
+    Withholding(u64),
+    /// Leg stream ids of a multi-token basket.
+    Basket(u64),
+    StreamBasket(u64),
+    FiatTerms(u64),
+    /// Reference and last accepted price of a token in a reference currency, keyed by (currency, token).
+    PriceAnchor(Symbol, Address),
+    /// Set once a stream past `end_ts` has had its outflow rate released ahead of closing.
+    RateReleased(u64),
+}
+
+/// Stream id indexes, stored one id per slot so they can be read in pages.
+#[contracttype]
+#[derive(Clone)]
+pub enum StreamIndex {
+    Employer(Address),
+    Worker(Address),
 }
 
 #[contracttype]
@@ -55,7 +100,136 @@ pub struct WithdrawResult {
     pub success: bool,
 }
 
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct StreamSummary {
+    pub stream_id: u64,
+    pub stream: Stream,
+}
+
+#[contracttype]
+#[derive(Clone, Debug)]
+pub struct StreamPage {
+    pub streams: Vec<StreamSummary>,
+    /// Slot to resume from, or `None` once the index is exhausted.
+    pub next_cursor: Option<u32>,
+}
+
+/// Payout token and vesting schedule of a new stream.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamParams {
+    pub token: Address,
+    pub rate: i128,
+    pub cliff_ts: u64,
+    pub start_ts: u64,
+    pub end_ts: u64,
+}
+
+/// Optional extras a constructor attaches to a new stream.
+#[derive(Default)]
+struct StreamOptions {
+    team_id: Option<u32>,
+    fiat: Option<FiatTerms>,
+}
+
+/// One token of a multi-token basket stream.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamLeg {
+    pub token: Address,
+    pub rate: i128,
+}
+
+/// Settlement terms of a stream whose amounts are denominated in `currency`
+/// rather than its payout token.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FiatTerms {
+    pub currency: Symbol,
+    /// Per-second rate in the payout token at creation, tracked by the vault and stats.
+    pub token_rate: i128,
+    /// Payout token liability still reserved in the vault for this stream.
+    pub reserve: i128,
+}
+
+/// Employer terms for paying workers ahead of vesting.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdvancePolicy {
+    /// Largest advance as a share of the next `window_days` of accrual, in basis points.
+    pub max_bps: u32,
+    pub window_days: u32,
+    /// Fee charged on the advance, in basis points, returned to the treasury.
+    pub fee_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum WithholdingAmount {
+    /// Share of each gross payout, in basis points.
+    Bps(u32),
+    /// Fixed amount per payout, limited to what is left of it.
+    Fixed(i128),
+}
+
+/// A tax or benefits deduction routed to `destination` on every payout of a stream.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithholdingRule {
+    pub destination: Address,
+    pub amount: WithholdingAmount,
+    pub label: Symbol,
+}
+
+/// One deduction taken from a payout, reported in the `payslip` event.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Deduction {
+    pub label: Symbol,
+    pub destination: Address,
+    pub amount: i128,
+}
+
+/// Running totals of an employer's streams in one token.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EmployerStats {
+    pub active_streams: u32,
+    /// Sum of the per-second rates of active streams.
+    pub burn_rate: i128,
+    /// Amount streams will pay out in total; canceling releases the unvested part.
+    pub total_committed: i128,
+    pub total_withdrawn: i128,
+}
+
+/// Running totals of a worker across all of their streams.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct WorkerStats {
+    pub active_streams: u32,
+    /// Sum of the per-second rates of active streams.
+    pub current_rate: i128,
+    pub total_earned: i128,
+}
+
+/// Mirrors `WorkforceRegistry`'s `PayoutShare` so payout routes can be decoded.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayoutShare {
+    pub recipient: Address,
+    pub bps: u32,
+}
+
 const DEFAULT_RETENTION_SECS: u64 = 30 * 24 * 60 * 60;
+const BPS_DENOMINATOR: i128 = 10_000;
+/// Maximum index slots a single page query inspects.
+const MAX_PAGE_SCAN: u32 = 100;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+/// How long a reference price holds before rolling to the latest accepted price.
+const PRICE_ANCHOR_PERIOD_SECS: u64 = SECONDS_PER_DAY;
+const MAX_WITHHOLDING_RULES: u32 = 10;
+const MAX_BASKET_LEGS: u32 = 5;
 
 #[contract]
 pub struct PayrollStream;
@@ -117,6 +291,133 @@ impl PayrollStream {
         env.storage().instance().set(&DataKey::Vault, &vault);
     }
 
+    /// Links a WorkforceRegistry so it tracks live streams per employer and worker.
+    pub fn set_registry(env: Env, registry: Address) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        env.storage().instance().set(&DataKey::Registry, &registry);
+    }
+
+    pub fn get_registry(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Registry)
+    }
+
+    /// Configures the swap adapter used to pay workers in their registry-preferred
+    /// token. Only workers who set a swap floor in the registry are swapped, and
+    /// only while the quote meets it; payouts whose swap would return less than
+    /// the floor or the quote minus `max_slippage_bps` revert. Everything else is
+    /// paid in the stream token.
+    pub fn set_swap_adapter(env: Env, adapter: Address, max_slippage_bps: u32) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        if i128::from(max_slippage_bps) > BPS_DENOMINATOR {
+            panic!("invalid slippage");
+        }
+        env.storage().instance().set(
+            &DataKey::SwapConfig,
+            &SwapConfig {
+                adapter,
+                max_slippage_bps,
+            },
+        );
+    }
+
+    /// Stops swapping payouts; workers are paid in the stream token again.
+    pub fn remove_swap_adapter(env: Env) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        env.storage().instance().remove(&DataKey::SwapConfig);
+    }
+
+    pub fn get_swap_adapter(env: Env) -> Option<SwapConfig> {
+        env.storage().instance().get(&DataKey::SwapConfig)
+    }
+
+    /// Configures the price feed settling streams denominated in `currency`.
+    /// Payouts revert when its price is older than `max_age_secs` or more than
+    /// `max_deviation_bps` away from the reference price, which rolls forward at
+    /// most once per day; new streams reserve `buffer_bps` on top of their
+    /// converted total. Cancellation falls back to the last accepted price.
+    pub fn set_price_oracle(
+        env: Env,
+        currency: Symbol,
+        oracle: Address,
+        max_age_secs: u64,
+        max_deviation_bps: u32,
+        buffer_bps: u32,
+    ) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        if max_age_secs == 0 {
+            panic!("invalid max age");
+        }
+        if i128::from(max_deviation_bps) > BPS_DENOMINATOR || i128::from(buffer_bps) > BPS_DENOMINATOR {
+            panic!("invalid bps");
+        }
+        env.storage().instance().set(
+            &DataKey::PriceOracle(currency),
+            &OracleConfig {
+                oracle,
+                max_age_secs,
+                max_deviation_bps,
+                buffer_bps,
+            },
+        );
+    }
+
+    pub fn get_price_oracle(env: Env, currency: Symbol) -> Option<OracleConfig> {
+        env.storage().instance().get(&DataKey::PriceOracle(currency))
+    }
+
+    /// Forgets the reference price of `token` in `currency`, so the next quote is
+    /// accepted after a genuine move larger than the deviation limit.
+    pub fn reset_price_anchor(env: Env, currency: Symbol, token: Address) {
+        let admin: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&StreamKey::PriceAnchor(currency, token));
+    }
+
+    /// Sets the registry attestation types (e.g., `KYC`) a worker must hold before
+    /// `employer` can stream to them. An empty list removes the requirement.
+    pub fn set_required_attestations(env: Env, employer: Address, attestation_types: Vec<Symbol>) {
+        employer.require_auth();
+        let key = StreamKey::RequiredAttestations(employer);
+        if attestation_types.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &attestation_types);
+        }
+    }
+
+    pub fn get_required_attestations(env: Env, employer: Address) -> Vec<Symbol> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::RequiredAttestations(employer))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
     pub fn create_stream(
         env: Env,
         employer: Address,
@@ -129,274 +430,708 @@ impl PayrollStream {
     ) -> Result<u64, QuipayError> {
         Self::require_not_paused(&env)?;
         employer.require_auth();
+        let params = StreamParams {
+            token,
+            rate,
+            cliff_ts,
+            start_ts,
+            end_ts,
+        };
+        Ok(Self::open_stream(
+            &env,
+            employer,
+            worker,
+            params,
+            StreamOptions::default(),
+        ))
+    }
 
-        if rate <= 0 {
-            panic!("rate must be positive");
-        }
-        if end_ts <= start_ts {
-            panic!("invalid time range");
-        }
+    /// Creates a stream on `employer`'s behalf. `caller` may be the employer or one
+    /// of its admins or sub-managers in the linked registry.
+    pub fn create_stream_for(
+        env: Env,
+        employer: Address,
+        caller: Address,
+        worker: Address,
+        params: StreamParams,
+    ) -> Result<u64, QuipayError> {
+        Self::require_not_paused(&env)?;
+        Self::require_employer_actor(&env, &employer, &caller);
+        Ok(Self::open_stream(
+            &env,
+            employer,
+            worker,
+            params,
+            StreamOptions::default(),
+        ))
+    }
 
-        let effective_cliff = if cliff_ts == 0 { start_ts } else { cliff_ts };
-        if effective_cliff > end_ts {
-            panic!("cliff_ts must not exceed end_ts");
-        }
+    /// Creates a stream tagged with a registry team. The registry rejects it unless
+    /// the worker is a member, the token matches the team budget token, and the
+    /// team's monthly burn stays within its cap.
+    pub fn create_team_stream(
+        env: Env,
+        employer: Address,
+        worker: Address,
+        params: StreamParams,
+        team_id: u32,
+    ) -> Result<u64, QuipayError> {
+        Self::require_not_paused(&env)?;
+        employer.require_auth();
+        let options = StreamOptions {
+            team_id: Some(team_id),
+            ..Default::default()
+        };
+        Ok(Self::open_stream(&env, employer, worker, params, options))
+    }
 
-        let now = env.ledger().timestamp();
-        if start_ts < now {
-            panic!("start_time must be >= current time");
+    /// Creates a basket paying `worker` in several tokens on one vesting schedule.
+    /// Each leg is an ordinary stream with its own liability in the vault; the
+    /// returned basket id withdraws or cancels all of them together.
+    pub fn create_basket_stream(
+        env: Env,
+        employer: Address,
+        worker: Address,
+        legs: Vec<StreamLeg>,
+        cliff_ts: u64,
+        start_ts: u64,
+        end_ts: u64,
+    ) -> Result<u64, QuipayError> {
+        Self::require_not_paused(&env)?;
+        employer.require_auth();
+        if legs.is_empty() || legs.len() > MAX_BASKET_LEGS {
+            panic!("invalid basket legs");
         }
 
-        let duration = end_ts - start_ts;
-        let total_amount = rate
-            .checked_mul(i128::from(duration as i64))
-            .expect("amount overflow");
-
-        let vault: Address = env
-            .storage()
-            .instance()
-            .get(&DataKey::Vault)
-            .expect("vault not configured");
-        use soroban_sdk::{vec, IntoVal, Symbol};
-        env.invoke_contract::<()>(
-            &vault,
-            &Symbol::new(&env, "add_liability"),
-            vec![&env, token.clone().into_val(&env), total_amount.into_val(&env)],
-        );
+        let mut tokens: Vec<Address> = Vec::new(&env);
+        let mut leg_ids: Vec<u64> = Vec::new(&env);
+        for leg in legs.iter() {
+            if tokens.contains(&leg.token) {
+                panic!("duplicate basket token");
+            }
+            tokens.push_back(leg.token.clone());
+            let params = StreamParams {
+                token: leg.token,
+                rate: leg.rate,
+                cliff_ts,
+                start_ts,
+                end_ts,
+            };
+            leg_ids.push_back(Self::open_stream(
+                &env,
+                employer.clone(),
+                worker.clone(),
+                params,
+                StreamOptions::default(),
+            ));
+        }
 
-        let mut next_id: u64 = env
+        let basket_id: u64 = env
             .storage()
             .instance()
-            .get(&DataKey::NextStreamId)
+            .get(&DataKey::NextBasketId)
             .unwrap_or(1u64);
-        let stream_id = next_id;
-        next_id = next_id.checked_add(1).expect("stream id overflow");
         env.storage()
             .instance()
-            .set(&DataKey::NextStreamId, &next_id);
-
-        let stream = Stream {
-            employer: employer.clone(),
-            worker: worker.clone(),
-            token: token.clone(),
-            rate,
-            cliff_ts: effective_cliff,
-            start_ts,
-            end_ts,
-            total_amount,
-            withdrawn_amount: 0,
-            last_withdrawal_ts: 0,
-            status: StreamStatus::Active,
-            created_at: now,
-            closed_at: 0,
-        };
-
+            .set(&DataKey::NextBasketId, &(basket_id + 1));
         env.storage()
             .persistent()
-            .set(&StreamKey::Stream(stream_id), &stream);
-
-        let emp_key = StreamKey::EmployerStreams(employer.clone());
-        let mut emp_ids: Vec<u64> = env
-            .storage()
-            .persistent()
-            .get(&emp_key)
-            .unwrap_or_else(|| Vec::new(&env));
-        emp_ids.push_back(stream_id);
-        env.storage().persistent().set(&emp_key, &emp_ids);
-
-        let wrk_key = StreamKey::WorkerStreams(worker.clone());
-        let mut wrk_ids: Vec<u64> = env
-            .storage()
-            .persistent()
-            .get(&wrk_key)
-            .unwrap_or_else(|| Vec::new(&env));
-        wrk_ids.push_back(stream_id);
-        env.storage().persistent().set(&wrk_key, &wrk_ids);
+            .set(&StreamKey::Basket(basket_id), &leg_ids);
+        for stream_id in leg_ids.iter() {
+            env.storage()
+                .persistent()
+                .set(&StreamKey::StreamBasket(stream_id), &basket_id);
+        }
 
+        #[allow(deprecated)]
         env.events().publish(
             (
                 Symbol::new(&env, "stream"),
-                Symbol::new(&env, "created"),
-                worker.clone(),
-                employer.clone(),
+                Symbol::new(&env, "basket_created"),
+                worker,
+                employer,
             ),
-            (stream_id, token, rate, start_ts, end_ts),
+            (basket_id, leg_ids),
         );
 
-        Ok(stream_id)
+        Ok(basket_id)
     }
 
-    pub fn withdraw(env: Env, stream_id: u64, worker: Address) -> Result<i128, QuipayError> {
+    /// Creates a stream whose `params.rate` is denominated in `currency` (e.g., `USD`)
+    /// and paid in `params.token` at the oracle price of each payout. Reference amounts
+    /// use the token's precision. The vault reserves the total converted at today's
+    /// price plus the oracle's buffer, topped up on payout if the price falls further.
+    pub fn create_fiat_stream(
+        env: Env,
+        employer: Address,
+        worker: Address,
+        params: StreamParams,
+        currency: Symbol,
+    ) -> Result<u64, QuipayError> {
         Self::require_not_paused(&env)?;
-        worker.require_auth();
-
-        let key = StreamKey::Stream(stream_id);
-        let mut stream: Stream = env
-            .storage()
-            .persistent()
-            .get(&key)
-            .expect("stream not found");
-
-        if stream.worker != worker {
-            panic!("not worker");
+        employer.require_auth();
+        let rate = params.rate;
+        if rate <= 0 {
+            panic!("rate must be positive");
         }
-        if Self::is_closed(&stream) {
-            panic!("stream closed");
+        if params.end_ts <= params.start_ts {
+            panic!("invalid time range");
         }
 
-        let now = env.ledger().timestamp();
-        let vested = Self::vested_amount(&stream, now);
-        let available = vested.checked_sub(stream.withdrawn_amount).unwrap_or(0);
-
-        if available <= 0 {
-            return Ok(0);
-        }
+        let config = Self::get_price_oracle(env.clone(), currency.clone())
+            .expect("price oracle not configured");
+        let quote = Self::quote(&env, &currency, &params.token);
+        let total = rate
+            .checked_mul(i128::from((params.end_ts - params.start_ts) as i64))
+            .expect("amount overflow");
+        let reserve = Self::to_token(total, quote)
+            .checked_mul(BPS_DENOMINATOR + i128::from(config.buffer_bps))
+            .expect("amount overflow")
+            / BPS_DENOMINATOR;
+        require!(reserve > 0, QuipayError::InvalidAmount);
+
+        let terms = FiatTerms {
+            currency,
+            token_rate: Self::to_token(rate, quote).max(1),
+            reserve,
+        };
+        let options = StreamOptions {
+            fiat: Some(terms),
+            ..Default::default()
+        };
+        Ok(Self::open_stream(&env, employer, worker, params, options))
+    }
 
-        stream.withdrawn_amount = stream
-            .withdrawn_amount
-            .checked_add(available)
-            .expect("withdrawn overflow");
-        stream.last_withdrawal_ts = now;
+    pub fn get_fiat_terms(env: Env, stream_id: u64) -> Option<FiatTerms> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::FiatTerms(stream_id))
+    }
 
-        if stream.withdrawn_amount >= stream.total_amount {
-            Self::close_stream_internal(&mut stream, now, StreamStatus::Completed);
-        }
+    pub fn get_basket(env: Env, basket_id: u64) -> Option<Vec<u64>> {
+        env.storage().persistent().get(&StreamKey::Basket(basket_id))
+    }
 
-        env.storage().persistent().set(&key, &stream);
+    pub fn get_stream_basket(env: Env, stream_id: u64) -> Option<u64> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::StreamBasket(stream_id))
+    }
 
-        env.events().publish(
-            (
-                Symbol::new(&env, "stream"),
-                Symbol::new(&env, "withdrawn"),
-                stream_id,
-                worker.clone(),
-            ),
-            (available, stream.token.clone()),
-        );
+    /// Withdraws every leg of a basket, reporting one result per leg.
+    pub fn withdraw_basket(env: Env, basket_id: u64, worker: Address) -> Result<Vec<WithdrawResult>, QuipayError> {
+        Self::require_not_paused(&env)?;
+        worker.require_auth();
+
+        let leg_ids = Self::get_basket(env.clone(), basket_id).ok_or(QuipayError::StreamNotFound)?;
+        Ok(Self::withdraw_each(&env, &leg_ids, &worker, None))
+    }
+
+    /// Cancels every leg of a basket. Legs share a schedule, so each releases
+    /// the same unvested share of its own token.
+    pub fn cancel_basket(env: Env, basket_id: u64, employer: Address) -> Result<(), QuipayError> {
+        Self::require_not_paused(&env)?;
+        employer.require_auth();
+
+        let leg_ids = Self::get_basket(env.clone(), basket_id).ok_or(QuipayError::StreamNotFound)?;
+        for stream_id in leg_ids.iter() {
+            Self::cancel(&env, stream_id, &employer);
+        }
+        Ok(())
+    }
+
+    pub fn get_stream_team(env: Env, stream_id: u64) -> Option<u32> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::StreamTeam(stream_id))
+    }
+
+    pub fn withdraw(env: Env, stream_id: u64, worker: Address) -> Result<i128, QuipayError> {
+        Self::require_not_paused(&env)?;
+        worker.require_auth();
 
-        Ok(available)
+        let stream = Self::load_worker_stream(&env, stream_id, &worker);
+        Ok(Self::withdraw_vested(&env, stream_id, stream, &worker, None))
     }
 
     pub fn batch_withdraw(env: Env, stream_ids: Vec<u64>, caller: Address) -> Vec<WithdrawResult> {
         Self::require_not_paused(&env).unwrap();
         caller.require_auth();
 
-        let now = env.ledger().timestamp();
-        let mut results: Vec<WithdrawResult> = Vec::new(&env);
+        Self::withdraw_each(&env, &stream_ids, &caller, None)
+    }
 
-        let mut idx = 0u32;
-        while idx < stream_ids.len() {
-            let stream_id = stream_ids.get(idx).unwrap();
-            let key = StreamKey::Stream(stream_id);
+    /// Pays every active stream in `employer`'s index slots `[start, start + limit)`
+    /// its vested amount, for end-of-period payroll runs. `limit` is capped at
+    /// `MAX_PAGE_SCAN`. Closed streams and fiat streams without a usable price are
+    /// reported as unsuccessful.
+    pub fn settle_employer_streams(
+        env: Env,
+        employer: Address,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<WithdrawResult>, QuipayError> {
+        Self::require_not_paused(&env)?;
+        employer.require_auth();
 
-            let result = match env.storage().persistent().get::<StreamKey, Stream>(&key) {
-                Some(mut stream) => {
-                    if stream.worker != caller {
-                        WithdrawResult {
-                            stream_id,
-                            amount: 0,
-                            success: false,
-                        }
-                    } else if Self::is_closed(&stream) {
-                        WithdrawResult {
-                            stream_id,
-                            amount: 0,
-                            success: false,
-                        }
-                    } else {
-                        let vested = Self::vested_amount(&stream, now);
-                        let available = vested.checked_sub(stream.withdrawn_amount).unwrap_or(0);
-
-                        if available <= 0 {
-                            WithdrawResult {
-                                stream_id,
-                                amount: 0,
-                                success: true,
-                            }
-                        } else {
-                            stream.withdrawn_amount = stream
-                                .withdrawn_amount
-                                .checked_add(available)
-                                .expect("withdrawn overflow");
-                            stream.last_withdrawal_ts = now;
-
-                            if stream.withdrawn_amount >= stream.total_amount {
-                                Self::close_stream_internal(
-                                    &mut stream,
-                                    now,
-                                    StreamStatus::Completed,
-                                );
-                            }
-
-                            env.storage().persistent().set(&key, &stream);
-
-                            env.events().publish(
-                                (
-                                    Symbol::new(&env, "stream"),
-                                    Symbol::new(&env, "withdrawn"),
-                                    stream_id,
-                                    caller.clone(),
-                                ),
-                                (available, stream.token.clone()),
-                            );
-
-                            WithdrawResult {
-                                stream_id,
-                                amount: available,
-                                success: true,
-                            }
-                        }
-                    }
-                }
-                None => WithdrawResult {
-                    stream_id,
-                    amount: 0,
-                    success: false,
-                },
+        Ok(Self::settle_streams(&env, &employer, start, limit))
+    }
+
+    /// `settle_employer_streams` run by the employer or one of its registry
+    /// admins or sub-managers.
+    pub fn settle_employer_streams_for(
+        env: Env,
+        employer: Address,
+        caller: Address,
+        start: u32,
+        limit: u32,
+    ) -> Result<Vec<WithdrawResult>, QuipayError> {
+        Self::require_not_paused(&env)?;
+        Self::require_employer_actor(&env, &employer, &caller);
+
+        Ok(Self::settle_streams(&env, &employer, start, limit))
+    }
+
+    /// Releases the vault outflow rate and burn-rate stats of active streams that
+    /// are past `end_ts` but still hold unwithdrawn funds, so runway projections
+    /// stop counting them. Their liability stays reserved until the worker
+    /// withdraws. Callable by anyone (e.g. a keeper); returns how many were released.
+    pub fn expire_streams(env: Env, stream_ids: Vec<u64>) -> u32 {
+        let now = env.ledger().timestamp();
+        let mut released: u32 = 0;
+        for stream_id in stream_ids.iter() {
+            let stream: Stream = match env
+                .storage()
+                .persistent()
+                .get(&StreamKey::Stream(stream_id))
+            {
+                Some(stream) => stream,
+                None => continue,
             };
+            let flag = StreamKey::RateReleased(stream_id);
+            if Self::is_closed(&stream) || now < stream.end_ts || env.storage().persistent().has(&flag) {
+                continue;
+            }
+            Self::release_rate(&env, stream_id, &stream);
+            env.storage().persistent().set(&flag, &true);
+            released += 1;
+        }
+        released
+    }
 
-            results.push_back(result);
-            idx += 1;
+    /// Lets `delegate` (a relayer, keeper or the automation gateway) trigger
+    /// withdrawals for `worker`. Funds still go to the worker's payout route;
+    /// the delegate may take up to `max_fee` of each withdrawal as reimbursement.
+    pub fn set_withdraw_delegate(env: Env, worker: Address, delegate: Address, max_fee: i128) {
+        worker.require_auth();
+        if max_fee < 0 {
+            panic!("invalid fee cap");
         }
+        env.storage().persistent().set(
+            &StreamKey::WithdrawDelegate(worker.clone(), delegate.clone()),
+            &max_fee,
+        );
 
-        results
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(&env, "stream"),
+                Symbol::new(&env, "delegate_set"),
+                worker,
+                delegate,
+            ),
+            max_fee,
+        );
+    }
+
+    pub fn revoke_withdraw_delegate(env: Env, worker: Address, delegate: Address) {
+        worker.require_auth();
+        env.storage()
+            .persistent()
+            .remove(&StreamKey::WithdrawDelegate(worker.clone(), delegate.clone()));
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(&env, "stream"),
+                Symbol::new(&env, "delegate_revoked"),
+                worker,
+            ),
+            delegate,
+        );
+    }
+
+    /// Returns the per-withdrawal fee cap if `delegate` may withdraw for `worker`.
+    pub fn get_withdraw_delegate(env: Env, worker: Address, delegate: Address) -> Option<i128> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::WithdrawDelegate(worker, delegate))
+    }
+
+    /// Withdraws vested funds of `worker`'s stream on their behalf. `fee` is paid
+    /// to the delegate out of the withdrawn amount and must not exceed its cap.
+    pub fn withdraw_for(
+        env: Env,
+        stream_id: u64,
+        worker: Address,
+        delegate: Address,
+        fee: i128,
+    ) -> Result<i128, QuipayError> {
+        Self::require_not_paused(&env)?;
+        delegate.require_auth();
+        Self::require_withdraw_delegate(&env, &worker, &delegate, fee)?;
+
+        let stream = Self::load_worker_stream(&env, stream_id, &worker);
+        Ok(Self::withdraw_vested(
+            &env,
+            stream_id,
+            stream,
+            &worker,
+            Some((delegate, fee)),
+        ))
+    }
+
+    /// Batch variant of `withdraw_for`; `fee` applies to each withdrawal.
+    pub fn batch_withdraw_for(
+        env: Env,
+        stream_ids: Vec<u64>,
+        worker: Address,
+        delegate: Address,
+        fee: i128,
+    ) -> Result<Vec<WithdrawResult>, QuipayError> {
+        Self::require_not_paused(&env)?;
+        delegate.require_auth();
+        Self::require_withdraw_delegate(&env, &worker, &delegate, fee)?;
+
+        Ok(Self::withdraw_each(
+            &env,
+            &stream_ids,
+            &worker,
+            Some((delegate, fee)),
+        ))
     }
 
     pub fn cancel_stream(env: Env, stream_id: u64, employer: Address) -> Result<(), QuipayError> {
         Self::require_not_paused(&env)?;
         employer.require_auth();
 
-        let key = StreamKey::Stream(stream_id);
-        let mut stream: Stream = env
+        Self::cancel(&env, stream_id, &employer);
+        Ok(())
+    }
+
+    /// `cancel_stream` run by the employer or one of its registry admins or
+    /// sub-managers.
+    pub fn cancel_stream_for(
+        env: Env,
+        stream_id: u64,
+        employer: Address,
+        caller: Address,
+    ) -> Result<(), QuipayError> {
+        Self::require_not_paused(&env)?;
+        Self::require_employer_actor(&env, &employer, &caller);
+
+        Self::cancel(&env, stream_id, &employer);
+        Ok(())
+    }
+
+    /// Sets the deductions withheld from every payout of a stream, applied in
+    /// order on the gross amount. An empty list removes withholding.
+    pub fn set_withholding(env: Env, employer: Address, stream_id: u64, rules: Vec<WithholdingRule>) {
+        employer.require_auth();
+        let stream: Stream = env
             .storage()
             .persistent()
-            .get(&key)
+            .get(&StreamKey::Stream(stream_id))
             .expect("stream not found");
-
         if stream.employer != employer {
             panic!("not employer");
         }
         if Self::is_closed(&stream) {
-            return Ok(());
+            panic!("stream closed");
+        }
+
+        let key = StreamKey::Withholding(stream_id);
+        if rules.is_empty() {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        if rules.len() > MAX_WITHHOLDING_RULES {
+            panic!("too many withholding rules");
+        }
+        let mut total_bps: i128 = 0;
+        for rule in rules.iter() {
+            match rule.amount {
+                WithholdingAmount::Bps(bps) => total_bps += i128::from(bps),
+                WithholdingAmount::Fixed(amount) => {
+                    if amount <= 0 {
+                        panic!("invalid withholding amount");
+                    }
+                }
+            }
+        }
+        if total_bps > BPS_DENOMINATOR {
+            panic!("invalid bps");
+        }
+        env.storage().persistent().set(&key, &rules);
+    }
+
+    pub fn get_withholding(env: Env, stream_id: u64) -> Vec<WithholdingRule> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::Withholding(stream_id))
+            .unwrap_or_else(|| Vec::new(&env))
+    }
+
+    /// Lets workers of `employer` draw advances against future accrual. A
+    /// `max_bps` of zero disables advances.
+    pub fn set_advance_policy(env: Env, employer: Address, max_bps: u32, window_days: u32, fee_bps: u32) {
+        employer.require_auth();
+        let key = StreamKey::AdvancePolicy(employer);
+        if max_bps == 0 {
+            env.storage().persistent().remove(&key);
+            return;
+        }
+        if i128::from(max_bps) > BPS_DENOMINATOR || i128::from(fee_bps) > BPS_DENOMINATOR {
+            panic!("invalid bps");
+        }
+        if window_days == 0 {
+            panic!("invalid advance window");
+        }
+        env.storage().persistent().set(
+            &key,
+            &AdvancePolicy {
+                max_bps,
+                window_days,
+                fee_bps,
+            },
+        );
+    }
+
+    pub fn get_advance_policy(env: Env, employer: Address) -> Option<AdvancePolicy> {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::AdvancePolicy(employer))
+    }
+
+    /// Pays `amount` of future accrual now. The advance plus its fee are repaid
+    /// from the stream's next accruals before anything becomes withdrawable
+    /// again; only one advance per stream may be outstanding.
+    pub fn request_advance(env: Env, stream_id: u64, worker: Address, amount: i128) -> Result<i128, QuipayError> {
+        Self::require_not_paused(&env)?;
+        worker.require_auth();
+        require!(amount > 0, QuipayError::InvalidAmount);
+
+        let mut stream = Self::load_worker_stream(&env, stream_id, &worker);
+        if Self::get_fiat_terms(env.clone(), stream_id).is_some() {
+            panic!("advances unsupported for fiat streams");
         }
+        let policy = Self::get_advance_policy(env.clone(), stream.employer.clone())
+            .expect("advances not enabled");
 
         let now = env.ledger().timestamp();
-        Self::close_stream_internal(&mut stream, now, StreamStatus::Canceled);
+        let vested = Self::vested_amount(&stream, now);
+        if stream.withdrawn_amount > vested {
+            panic!("advance outstanding");
+        }
+
+        let window_secs = u64::from(policy.window_days) * SECONDS_PER_DAY;
+        let upcoming = stream
+            .rate
+            .saturating_mul(i128::from(window_secs))
+            .min(stream.total_amount - vested);
+        let max_advance = upcoming * i128::from(policy.max_bps) / BPS_DENOMINATOR;
+        require!(amount <= max_advance, QuipayError::InvalidAmount);
+
+        // Settle what is already earned so the advance starts from a clean slate
+        let owed = vested - stream.withdrawn_amount;
+        let fee = amount * i128::from(policy.fee_bps) / BPS_DENOMINATOR;
+        let repay_to = vested + amount + fee;
+        // The stream must keep accruing past the advance to complete normally
+        if repay_to >= stream.total_amount {
+            return Err(QuipayError::InvalidAmount);
+        }
+
+        stream.withdrawn_amount = repay_to;
+        stream.last_withdrawal_ts = now;
+        env.storage()
+            .persistent()
+            .set(&StreamKey::Stream(stream_id), &stream);
+
+        Self::settle(&env, stream_id, &stream, owed + amount, None, false);
+        if fee > 0 {
+            Self::vault_call(&env, "remove_liability", &stream.token, fee);
+            Self::update_employer_stats(&env, &stream.employer, &stream.token, |stats| {
+                stats.total_committed -= fee;
+            });
+        }
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(&env, "stream"),
+                Symbol::new(&env, "advanced"),
+                stream_id,
+                worker,
+            ),
+            (amount, fee, owed),
+        );
+
+        Ok(owed + amount)
+    }
+
+    /// Portion of an advance (including its fee) not yet repaid by accrual.
+    pub fn get_advance_outstanding(env: Env, stream_id: u64) -> i128 {
+        let stream: Stream = env
+            .storage()
+            .persistent()
+            .get(&StreamKey::Stream(stream_id))
+            .expect("stream not found");
+        let vested = Self::vested_amount(&stream, env.ledger().timestamp());
+        (stream.withdrawn_amount - vested).max(0)
+    }
+
+    /// Requires `employer` to co-sign every `transfer_stream` of its streams.
+    pub fn set_transfer_consent(env: Env, employer: Address, required: bool) {
+        employer.require_auth();
+        let key = StreamKey::TransferConsent(employer);
+        if required {
+            env.storage().persistent().set(&key, &true);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+
+    pub fn requires_transfer_consent(env: Env, employer: Address) -> bool {
+        env.storage()
+            .persistent()
+            .has(&StreamKey::TransferConsent(employer))
+    }
+
+    /// Reassigns an active stream, including everything not yet withdrawn, to
+    /// `new_worker` (a new wallet or the buyer of the receivable). Authorized by
+    /// the current worker, plus the employer if it requires transfer consent.
+    /// `new_worker` need not be registered in the linked registry.
+    pub fn transfer_stream(env: Env, stream_id: u64, new_worker: Address) -> Result<(), QuipayError> {
+        Self::require_not_paused(&env)?;
+
+        let key = StreamKey::Stream(stream_id);
+        let mut stream: Stream = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .ok_or(QuipayError::StreamNotFound)?;
+        let old_worker = stream.worker.clone();
+        old_worker.require_auth();
+        if Self::requires_transfer_consent(env.clone(), stream.employer.clone()) {
+            stream.employer.require_auth();
+        }
+        require!(!Self::is_closed(&stream), QuipayError::StreamClosed);
+        require!(new_worker != old_worker, QuipayError::InvalidAddress);
+
+        Self::require_attestations(&env, &stream.employer, &new_worker);
+
+        stream.worker = new_worker.clone();
         env.storage().persistent().set(&key, &stream);
 
+        Self::index_remove(&env, &StreamIndex::Worker(old_worker.clone()), stream_id);
+        Self::index_push(&env, &StreamIndex::Worker(new_worker.clone()), stream_id);
+        let rate = if env.storage().persistent().has(&StreamKey::RateReleased(stream_id)) {
+            0
+        } else {
+            Self::token_rate(&env, stream_id, &stream)
+        };
+        Self::update_worker_stats(&env, &old_worker, |stats| {
+            stats.active_streams = stats.active_streams.saturating_sub(1);
+            stats.current_rate -= rate;
+        });
+        Self::update_worker_stats(&env, &new_worker, |stats| {
+            stats.active_streams += 1;
+            stats.current_rate += rate;
+        });
+        Self::registry_notify(&env, "stream_closed", &stream.employer, &old_worker);
+        // Buyers of a receivable are often not registered workers; the registry
+        // only tracks registered ones
+        if Self::is_registered_worker(&env, &new_worker) {
+            Self::registry_notify(&env, "stream_opened", &stream.employer, &new_worker);
+        }
+
+        #[allow(deprecated)]
         env.events().publish(
             (
                 Symbol::new(&env, "stream"),
-                Symbol::new(&env, "canceled"),
+                Symbol::new(&env, "transferred"),
                 stream_id,
-                employer.clone(),
             ),
-            (stream.worker.clone(), stream.token.clone()),
+            (old_worker, new_worker),
         );
 
         Ok(())
     }
 
+    /// Repoints the streams of `old_worker` held in index slots starting at
+    /// `cursor` to `new_worker` during account recovery, at most `MAX_PAGE_SCAN`
+    /// slots per call. Only the linked registry can call this. Returns the
+    /// employer of each live stream moved so the registry can update its
+    /// tracking, and the slot to resume from (`None` once every stream moved).
+    pub fn migrate_worker(
+        env: Env,
+        old_worker: Address,
+        new_worker: Address,
+        cursor: u32,
+    ) -> (Vec<Address>, Option<u32>) {
+        let registry: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Registry)
+            .expect("registry not configured");
+        registry.require_auth();
+
+        let old_index = StreamIndex::Worker(old_worker.clone());
+        let new_index = StreamIndex::Worker(new_worker.clone());
+        let ids = Self::index_ids(&env, &old_index, cursor, MAX_PAGE_SCAN);
+        let slots = Self::index_slots(&env, &old_index);
+        let scan_end = cursor.saturating_add(MAX_PAGE_SCAN).min(slots);
+
+        let mut live_employers: Vec<Address> = Vec::new(&env);
+        for stream_id in ids.iter() {
+            let key = StreamKey::Stream(stream_id);
+            if let Some(mut stream) = env.storage().persistent().get::<StreamKey, Stream>(&key) {
+                stream.worker = new_worker.clone();
+                env.storage().persistent().set(&key, &stream);
+                if !Self::is_closed(&stream) {
+                    live_employers.push_back(stream.employer);
+                }
+                Self::index_push(&env, &new_index, stream_id);
+            }
+            Self::index_remove(&env, &old_index, stream_id);
+        }
+
+        let old_stats_key = StreamKey::WorkerStats(old_worker.clone());
+        let old_stats: Option<WorkerStats> = env.storage().persistent().get(&old_stats_key);
+        if let Some(moved) = old_stats {
+            env.storage().persistent().remove(&old_stats_key);
+            Self::update_worker_stats(&env, &new_worker, |stats| {
+                stats.active_streams += moved.active_streams;
+                stats.current_rate += moved.current_rate;
+                stats.total_earned += moved.total_earned;
+            });
+        }
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(&env, "stream"),
+                Symbol::new(&env, "migrated"),
+                old_worker,
+                new_worker,
+            ),
+            ids.len(),
+        );
+
+        let next_cursor = if scan_end < slots { Some(scan_end) } else { None };
+        (live_employers, next_cursor)
+    }
+
     pub fn get_stream(env: Env, stream_id: u64) -> Option<Stream> {
         env.storage()
             .persistent()
@@ -422,19 +1157,69 @@ impl PayrollStream {
         vested.checked_sub(stream.withdrawn_amount).unwrap_or(0).max(0)
     }
 
-    pub fn cleanup_stream(env: Env, stream_id: u64) {
+    /// Stream ids in the first `MAX_PAGE_SCAN` slots of `employer`'s index.
+    ///
+    /// Deprecated: longer histories are truncated; use `get_employer_streams_page`.
     pub fn get_employer_streams(env: Env, employer: Address) -> Vec<u64> {
+        Self::index_ids(&env, &StreamIndex::Employer(employer), 0, MAX_PAGE_SCAN)
+    }
+
+    /// Stream ids in the first `MAX_PAGE_SCAN` slots of `worker`'s index.
+    ///
+    /// Deprecated: longer histories are truncated; use `get_worker_streams_page`.
+    pub fn get_worker_streams(env: Env, worker: Address) -> Vec<u64> {
+        Self::index_ids(&env, &StreamIndex::Worker(worker), 0, MAX_PAGE_SCAN)
+    }
+
+    /// Returns up to `limit` (at least 1) of `employer`'s streams starting at index
+    /// slot `cursor`, optionally only those with `status_filter`. At most
+    /// `MAX_PAGE_SCAN` slots are inspected per call; continue from `next_cursor`
+    /// until it is `None`.
+    pub fn get_employer_streams_page(
+        env: Env,
+        employer: Address,
+        cursor: u32,
+        limit: u32,
+        status_filter: Option<StreamStatus>,
+    ) -> StreamPage {
+        Self::index_page(
+            &env,
+            &StreamIndex::Employer(employer),
+            cursor,
+            limit,
+            status_filter,
+        )
+    }
+
+    /// Worker counterpart of `get_employer_streams_page`.
+    pub fn get_worker_streams_page(
+        env: Env,
+        worker: Address,
+        cursor: u32,
+        limit: u32,
+        status_filter: Option<StreamStatus>,
+    ) -> StreamPage {
+        Self::index_page(
+            &env,
+            &StreamIndex::Worker(worker),
+            cursor,
+            limit,
+            status_filter,
+        )
+    }
+
+    pub fn get_employer_stats(env: Env, employer: Address, token: Address) -> EmployerStats {
         env.storage()
             .persistent()
-            .get(&StreamKey::EmployerStreams(employer))
-            .unwrap_or_else(|| Vec::new(&env))
+            .get(&StreamKey::EmployerStats(employer, token))
+            .unwrap_or_default()
     }
 
-    pub fn get_worker_streams(env: Env, worker: Address) -> Vec<u64> {
+    pub fn get_worker_stats(env: Env, worker: Address) -> WorkerStats {
         env.storage()
             .persistent()
-            .get(&StreamKey::WorkerStreams(worker))
-            .unwrap_or_else(|| Vec::new(&env))
+            .get(&StreamKey::WorkerStats(worker))
+            .unwrap_or_default()
     }
 
     pub fn cleanup_stream(env: Env, stream_id: u64) -> Result<(), QuipayError> {
@@ -458,8 +1243,8 @@ impl PayrollStream {
             panic!("retention period not met");
         }
 
-        Self::remove_from_index(&env, StreamKey::EmployerStreams(stream.employer), stream_id);
-        Self::remove_from_index(&env, StreamKey::WorkerStreams(stream.worker), stream_id);
+        Self::index_remove(&env, &StreamIndex::Employer(stream.employer), stream_id);
+        Self::index_remove(&env, &StreamIndex::Worker(stream.worker), stream_id);
 
         env.storage().persistent().remove(&key);
         Ok(())
@@ -477,56 +1262,1055 @@ impl PayrollStream {
         Ok(())
     }
 
-    fn is_closed(stream: &Stream) -> bool {
-        stream.status == StreamStatus::Canceled || stream.status == StreamStatus::Completed
-    }
-
-    fn close_stream_internal(stream: &mut Stream, now: u64, status: StreamStatus) {
-        stream.status = status;
-        stream.closed_at = now;
+    fn vault(env: &Env) -> Address {
+        env.storage()
+            .instance()
+            .get(&DataKey::Vault)
+            .expect("vault not configured")
     }
 
-    fn remove_from_index(env: &Env, key: StreamKey, stream_id: u64) {
-        let ids: Vec<u64> = match env.storage().persistent().get(&key) {
-            Some(v) => v,
-            None => return,
-        };
-        let mut new_ids: Vec<u64> = Vec::new(env);
-        let mut i = 0u32;
-        while i < ids.len() {
-            let id = ids.get(i).unwrap();
-            if id != stream_id {
-                new_ids.push_back(id);
-            }
-            i += 1;
-        }
-        if new_ids.len() == 0 {
-            env.storage().persistent().remove(&key);
-        } else {
-            env.storage().persistent().set(&key, &new_ids);
-        }
+    /// Invokes a vault entry point shaped `(caller, token, amount)` on this contract's behalf.
+    fn vault_call(env: &Env, func: &str, token: &Address, amount: i128) {
+        let vault = Self::vault(env);
+        env.invoke_contract::<()>(
+            &vault,
+            &Symbol::new(env, func),
+            vec![
+                env,
+                env.current_contract_address().into_val(env),
+                token.clone().into_val(env),
+                amount.into_val(env),
+            ],
+        );
     }
 
-    fn vested_amount(stream: &Stream, now: u64) -> i128 {
-        Self::vested_amount_at(stream, now)
+    /// Pays `amount` to `to` out of the liability this contract reserved in the vault.
+    fn vault_payout(env: &Env, to: &Address, token: &Address, amount: i128) {
+        let vault = Self::vault(env);
+        env.invoke_contract::<()>(
+            &vault,
+            &Symbol::new(env, "payout_liability"),
+            vec![
+                env,
+                env.current_contract_address().into_val(env),
+                to.clone().into_val(env),
+                token.clone().into_val(env),
+                amount.into_val(env),
+            ],
+        );
     }
 
-    fn vested_amount_at(stream: &Stream, timestamp: u64) -> i128 {
-        let is_canceled = (stream.status_bits & (1u32 << (StreamStatus::Canceled as u32))) != 0;
-        let is_completed = (stream.status_bits & (1u32 << (StreamStatus::Completed as u32))) != 0;
-        let is_closed = is_canceled || is_completed;
-
+    fn open_stream(
+        env: &Env,
+        employer: Address,
+        worker: Address,
+        params: StreamParams,
+        options: StreamOptions,
+    ) -> u64 {
+        let StreamParams {
+            token,
+            rate,
+            cliff_ts,
+            start_ts,
+            end_ts,
+        } = params;
+        let StreamOptions { team_id, fiat } = options;
+        if rate <= 0 {
+            panic!("rate must be positive");
+        }
+        if end_ts <= start_ts {
+            panic!("invalid time range");
+        }
+
+        Self::require_attestations(env, &employer, &worker);
+
+        let effective_cliff = if cliff_ts == 0 { start_ts } else { cliff_ts };
+        if effective_cliff > end_ts {
+            panic!("cliff_ts must not exceed end_ts");
+        }
+
+        let now = env.ledger().timestamp();
+        if start_ts < now {
+            panic!("start_time must be >= current time");
+        }
+
+        let duration = end_ts - start_ts;
+        let total_amount = rate
+            .checked_mul(i128::from(duration as i64))
+            .expect("amount overflow");
+
+        // Fiat streams are tracked by the vault in the payout token
+        let (token_rate, liability) = match &fiat {
+            Some(terms) => (terms.token_rate, terms.reserve),
+            None => (rate, total_amount),
+        };
+
+        // Rate first so the vault's reserve check sees the new outflow
+        Self::vault_call(env, "add_outflow_rate", &token, token_rate);
+        Self::vault_call(env, "add_liability", &token, liability);
+        Self::registry_notify(env, "stream_opened", &employer, &worker);
+        if let Some(team_id) = team_id {
+            Self::registry_team_call(
+                env,
+                "team_stream_opened",
+                vec![
+                    env,
+                    team_id.into_val(env),
+                    employer.clone().into_val(env),
+                    worker.clone().into_val(env),
+                    token.clone().into_val(env),
+                    rate.into_val(env),
+                ],
+            );
+        }
+
+        let mut next_id: u64 = env
+            .storage()
+            .instance()
+            .get(&DataKey::NextStreamId)
+            .unwrap_or(1u64);
+        let stream_id = next_id;
+        next_id = next_id.checked_add(1).expect("stream id overflow");
+        env.storage()
+            .instance()
+            .set(&DataKey::NextStreamId, &next_id);
+
+        let stream = Stream {
+            employer: employer.clone(),
+            worker: worker.clone(),
+            token: token.clone(),
+            rate,
+            cliff_ts: effective_cliff,
+            start_ts,
+            end_ts,
+            total_amount,
+            withdrawn_amount: 0,
+            last_withdrawal_ts: 0,
+            status: StreamStatus::Active,
+            created_at: now,
+            closed_at: 0,
+        };
+
+        env.storage()
+            .persistent()
+            .set(&StreamKey::Stream(stream_id), &stream);
+        if let Some(team_id) = team_id {
+            env.storage()
+                .persistent()
+                .set(&StreamKey::StreamTeam(stream_id), &team_id);
+        }
+        if let Some(terms) = fiat {
+            env.storage()
+                .persistent()
+                .set(&StreamKey::FiatTerms(stream_id), &terms);
+        }
+
+        Self::index_push(env, &StreamIndex::Employer(employer.clone()), stream_id);
+        Self::index_push(env, &StreamIndex::Worker(worker.clone()), stream_id);
+        Self::update_employer_stats(env, &employer, &token, |stats| {
+            stats.active_streams += 1;
+            stats.burn_rate += token_rate;
+            stats.total_committed += liability;
+        });
+        Self::update_worker_stats(env, &worker, |stats| {
+            stats.active_streams += 1;
+            stats.current_rate += token_rate;
+        });
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(env, "stream"),
+                Symbol::new(env, "created"),
+                worker.clone(),
+                employer.clone(),
+            ),
+            (stream_id, token, rate, start_ts, end_ts),
+        );
+
+        stream_id
+    }
+
+    /// Pays `amount` of the stream token owed to `worker`, swapping it into the
+    /// worker's preferred token when the registry and swap adapter provide a route,
+    /// and splitting it across the worker's registry payout route if one is set.
+    fn pay_worker(env: &Env, worker: &Address, token: &Address, amount: i128) {
+        let shares = Self::payout_route(env, worker);
+
+        let (config, preferred, min_out) = match Self::swap_route(env, worker, token, amount) {
+            Some(route) => route,
+            None => {
+                if shares.is_empty() {
+                    Self::vault_payout(env, worker, token, amount);
+                } else {
+                    for (recipient, part) in Self::split_payout(env, &shares, amount).iter() {
+                        Self::vault_payout(env, &recipient, token, part);
+                    }
+                }
+                return;
+            }
+        };
+
+        let this = env.current_contract_address();
+        Self::vault_payout(env, &this, token, amount);
+        token::Client::new(env, token).transfer(&this, &config.adapter, &amount);
+
+        let swap_to = if shares.is_empty() { worker.clone() } else { this.clone() };
+        let received = SwapAdapterClient::new(env, &config.adapter).swap(
+            token,
+            &preferred,
+            &amount,
+            &min_out,
+            &swap_to,
+        );
+
+        if !shares.is_empty() {
+            let preferred_client = token::Client::new(env, &preferred);
+            for (recipient, part) in Self::split_payout(env, &shares, received).iter() {
+                preferred_client.transfer(&this, &recipient, &part);
+            }
+        }
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(env, "stream"),
+                Symbol::new(env, "swapped"),
+                worker.clone(),
+            ),
+            (token.clone(), amount, preferred, received),
+        );
+    }
+
+    /// The worker's payout route from the linked registry; empty means pay the worker.
+    fn payout_route(env: &Env, worker: &Address) -> Vec<PayoutShare> {
+        match env.storage().instance().get::<DataKey, Address>(&DataKey::Registry) {
+            Some(registry) => env.invoke_contract(
+                &registry,
+                &Symbol::new(env, "get_payout_route"),
+                vec![env, worker.clone().into_val(env)],
+            ),
+            None => Vec::new(env),
+        }
+    }
+
+    /// Splits `amount` by basis points; rounding dust goes to the last recipient.
+    /// Zero-sized parts are dropped.
+    fn split_payout(env: &Env, shares: &Vec<PayoutShare>, amount: i128) -> Vec<(Address, i128)> {
+        let mut parts: Vec<(Address, i128)> = Vec::new(env);
+        let mut remaining = amount;
+        let last = shares.len() - 1;
+        for (i, share) in shares.iter().enumerate() {
+            let part = if i as u32 == last {
+                remaining
+            } else {
+                amount
+                    .checked_mul(i128::from(share.bps))
+                    .expect("payout overflow")
+                    / BPS_DENOMINATOR
+            };
+            remaining -= part;
+            if part > 0 {
+                parts.push_back((share.recipient, part));
+            }
+        }
+        parts
+    }
+
+    /// Returns the adapter, target token and minimum output when `worker` prefers a
+    /// different token, has set a swap floor for `token`, and the adapter quotes at
+    /// or above it; `None` means pay directly.
+    fn swap_route(
+        env: &Env,
+        worker: &Address,
+        token: &Address,
+        amount: i128,
+    ) -> Option<(SwapConfig, Address, i128)> {
+        let config: SwapConfig = env.storage().instance().get(&DataKey::SwapConfig)?;
+        let registry: Address = env.storage().instance().get(&DataKey::Registry)?;
+
+        let preferred: Option<Address> = env.invoke_contract(
+            &registry,
+            &Symbol::new(env, "get_preferred_token"),
+            vec![env, worker.clone().into_val(env)],
+        );
+        let preferred = preferred?;
+        if preferred == *token {
+            return None;
+        }
+
+        // The adapter's own quote can move with the pool it trades against, so the
+        // binding minimum is the rate the worker accepts, set in the registry.
+        let floor: Option<i128> = env.invoke_contract(
+            &registry,
+            &Symbol::new(env, "get_swap_floor"),
+            vec![env, worker.clone().into_val(env), token.clone().into_val(env)],
+        );
+        let floor_out = amount.checked_mul(floor?).expect("quote overflow") / BPS_DENOMINATOR;
+
+        let quoted = match SwapAdapterClient::new(env, &config.adapter).try_quote(
+            token,
+            &preferred,
+            &amount,
+        ) {
+            Ok(Ok(quoted)) if quoted > 0 && quoted >= floor_out => quoted,
+            _ => return None,
+        };
+        let slippage_out = quoted
+            .checked_mul(BPS_DENOMINATOR - i128::from(config.max_slippage_bps))
+            .expect("quote overflow")
+            / BPS_DENOMINATOR;
+
+        Some((config, preferred, floor_out.max(slippage_out)))
+    }
+
+    /// Panics unless `worker` holds every attestation `employer` requires.
+    fn require_attestations(env: &Env, employer: &Address, worker: &Address) {
+        let required = Self::get_required_attestations(env.clone(), employer.clone());
+        if required.is_empty() {
+            return;
+        }
+        let registry: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Registry)
+            .expect("registry not configured");
+        let attested: bool = env.invoke_contract(
+            &registry,
+            &Symbol::new(env, "has_attestations"),
+            vec![env, worker.clone().into_val(env), required.into_val(env)],
+        );
+        if !attested {
+            panic!("missing required attestation");
+        }
+    }
+
+    /// Settles what `employer`'s stream has vested and returns the rest to the treasury.
+    fn cancel(env: &Env, stream_id: u64, employer: &Address) {
+        let key = StreamKey::Stream(stream_id);
+        let mut stream: Stream = env
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("stream not found");
+
+        if stream.employer != *employer {
+            panic!("not employer");
+        }
+        if Self::is_closed(&stream) {
+            return;
+        }
+
+        let now = env.ledger().timestamp();
+        let vested = Self::vested_amount(&stream, now);
+        let owed = vested.checked_sub(stream.withdrawn_amount).unwrap_or(0).max(0);
+        // An unrepaid advance was already paid out of the liability, so only the
+        // part beyond it goes back; the advance itself is written off.
+        let unvested = stream
+            .total_amount
+            .checked_sub(vested.max(stream.withdrawn_amount))
+            .unwrap_or(0)
+            .max(0);
+        let written_off = stream.withdrawn_amount.checked_sub(vested).unwrap_or(0).max(0);
+
+        // Settle what the worker has already earned and hand the rest back to the treasury
+        stream.withdrawn_amount = stream
+            .withdrawn_amount
+            .checked_add(owed)
+            .expect("withdrawn overflow");
+        if owed > 0 {
+            stream.last_withdrawal_ts = now;
+        }
+        Self::close_stream_internal(&mut stream, now, StreamStatus::Canceled);
+        env.storage().persistent().set(&key, &stream);
+
+        if owed > 0 {
+            Self::settle(env, stream_id, &stream, owed, None, true);
+        }
+        // Fiat streams hand back their whole remaining reserve in `release`
+        let fiat = env.storage().persistent().has(&StreamKey::FiatTerms(stream_id));
+        if unvested > 0 && !fiat {
+            Self::vault_call(env, "remove_liability", &stream.token, unvested);
+            Self::update_employer_stats(env, employer, &stream.token, |stats| {
+                stats.total_committed -= unvested;
+            });
+        }
+        Self::release(env, stream_id, &stream);
+        if written_off > 0 {
+            #[allow(deprecated)]
+            env.events().publish(
+                (
+                    Symbol::new(env, "stream"),
+                    Symbol::new(env, "advance_written_off"),
+                    stream_id,
+                ),
+                written_off,
+            );
+        }
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(env, "stream"),
+                Symbol::new(env, "canceled"),
+                stream_id,
+                employer.clone(),
+            ),
+            (stream.worker.clone(), stream.token.clone()),
+        );
+    }
+
+    /// Pays every active stream in `employer`'s index slots `[start, start + limit)`.
+    fn settle_streams(env: &Env, employer: &Address, start: u32, limit: u32) -> Vec<WithdrawResult> {
+        let ids = Self::index_ids(
+            env,
+            &StreamIndex::Employer(employer.clone()),
+            start,
+            limit.min(MAX_PAGE_SCAN),
+        );
+        let mut results: Vec<WithdrawResult> = Vec::new(env);
+
+        for stream_id in ids.iter() {
+            let result = match env
+                .storage()
+                .persistent()
+                .get::<StreamKey, Stream>(&StreamKey::Stream(stream_id))
+            {
+                Some(stream)
+                    if stream.employer == *employer
+                        && !Self::is_closed(&stream)
+                        && Self::price_available(env, stream_id, &stream) =>
+                {
+                    let worker = stream.worker.clone();
+                    WithdrawResult {
+                        stream_id,
+                        amount: Self::withdraw_vested(env, stream_id, stream, &worker, None),
+                        success: true,
+                    }
+                }
+                _ => WithdrawResult {
+                    stream_id,
+                    amount: 0,
+                    success: false,
+                },
+            };
+            results.push_back(result);
+        }
+
+        results
+    }
+
+    /// Authorizes `caller` to act for `employer`: the employer itself, or one of
+    /// its admins or sub-managers in the linked registry.
+    fn require_employer_actor(env: &Env, employer: &Address, caller: &Address) {
+        caller.require_auth();
+        if caller == employer {
+            return;
+        }
+        let registry: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Registry)
+            .expect("registry not configured");
+        let allowed: bool = env.invoke_contract(
+            &registry,
+            &Symbol::new(env, "can_act_for"),
+            vec![env, employer.into_val(env), caller.into_val(env)],
+        );
+        if !allowed {
+            panic!("not employer");
+        }
+    }
+
+    fn load_worker_stream(env: &Env, stream_id: u64, worker: &Address) -> Stream {
+        let stream: Stream = env
+            .storage()
+            .persistent()
+            .get(&StreamKey::Stream(stream_id))
+            .expect("stream not found");
+
+        if stream.worker != *worker {
+            panic!("not worker");
+        }
+        if Self::is_closed(&stream) {
+            panic!("stream closed");
+        }
+        stream
+    }
+
+    fn require_withdraw_delegate(
+        env: &Env,
+        worker: &Address,
+        delegate: &Address,
+        fee: i128,
+    ) -> Result<(), QuipayError> {
+        let max_fee = Self::get_withdraw_delegate(env.clone(), worker.clone(), delegate.clone())
+            .ok_or(QuipayError::NotAuthorized)?;
+        require!(fee >= 0 && fee <= max_fee, QuipayError::InvalidAmount);
+        Ok(())
+    }
+
+    /// Withdraws everything vested on a validated stream, paying `relayer_fee`
+    /// out of it when a delegate triggered the withdrawal.
+    fn withdraw_vested(
+        env: &Env,
+        stream_id: u64,
+        mut stream: Stream,
+        worker: &Address,
+        relayer_fee: Option<(Address, i128)>,
+    ) -> i128 {
+        let now = env.ledger().timestamp();
+        let vested = Self::vested_amount(&stream, now);
+        let available = vested.checked_sub(stream.withdrawn_amount).unwrap_or(0);
+
+        if available <= 0 {
+            return 0;
+        }
+
+        stream.withdrawn_amount = stream
+            .withdrawn_amount
+            .checked_add(available)
+            .expect("withdrawn overflow");
+        stream.last_withdrawal_ts = now;
+
+        if stream.withdrawn_amount >= stream.total_amount {
+            Self::close_stream_internal(&mut stream, now, StreamStatus::Completed);
+        }
+
+        env.storage()
+            .persistent()
+            .set(&StreamKey::Stream(stream_id), &stream);
+        let paid = Self::settle(env, stream_id, &stream, available, relayer_fee, false);
+        if stream.status == StreamStatus::Completed {
+            Self::release(env, stream_id, &stream);
+        }
+
+        #[allow(deprecated)]
+        env.events().publish(
+            (
+                Symbol::new(env, "stream"),
+                Symbol::new(env, "withdrawn"),
+                stream_id,
+                worker.clone(),
+            ),
+            (paid, stream.token.clone()),
+        );
+
+        paid
+    }
+
+    /// Whether a batch can pay `stream` now. Fiat streams with something owed need
+    /// a usable oracle price, since `draw_fiat_reserve` reverts without one.
+    fn price_available(env: &Env, stream_id: u64, stream: &Stream) -> bool {
+        let Some(terms) = Self::get_fiat_terms(env.clone(), stream_id) else {
+            return true;
+        };
+        let owed = Self::vested_amount(stream, env.ledger().timestamp()) - stream.withdrawn_amount;
+        owed <= 0 || Self::checked_quote(env, &terms.currency, &stream.token).is_ok()
+    }
+
+    /// Withdraws from each of `worker`'s streams, reporting failures (including fiat
+    /// streams without a usable price) instead of panicking.
+    fn withdraw_each(
+        env: &Env,
+        stream_ids: &Vec<u64>,
+        worker: &Address,
+        relayer_fee: Option<(Address, i128)>,
+    ) -> Vec<WithdrawResult> {
+        let mut results: Vec<WithdrawResult> = Vec::new(env);
+
+        for stream_id in stream_ids.iter() {
+            let result = match env
+                .storage()
+                .persistent()
+                .get::<StreamKey, Stream>(&StreamKey::Stream(stream_id))
+            {
+                Some(stream)
+                    if stream.worker == *worker
+                        && !Self::is_closed(&stream)
+                        && Self::price_available(env, stream_id, &stream) =>
+                {
+                    WithdrawResult {
+                        stream_id,
+                        amount: Self::withdraw_vested(
+                            env,
+                            stream_id,
+                            stream,
+                            worker,
+                            relayer_fee.clone(),
+                        ),
+                        success: true,
+                    }
+                }
+                _ => WithdrawResult {
+                    stream_id,
+                    amount: 0,
+                    success: false,
+                },
+            };
+            results.push_back(result);
+        }
+
+        results
+    }
+
+    /// Pays `amount` of a stream to its worker and credits the stream's team spend.
+    /// Withholding comes off the gross first, then a delegate's `relayer_fee`,
+    /// capped at what is left.
+    fn settle(
+        env: &Env,
+        stream_id: u64,
+        stream: &Stream,
+        amount: i128,
+        relayer_fee: Option<(Address, i128)>,
+        price_fallback: bool,
+    ) -> i128 {
+        let amount = match Self::get_fiat_terms(env.clone(), stream_id) {
+            Some(terms) => {
+                Self::draw_fiat_reserve(env, stream_id, stream, terms, amount, price_fallback)
+            }
+            None => amount,
+        };
+        let rules = Self::get_withholding(env.clone(), stream_id);
+        let mut deductions: Vec<Deduction> = Vec::new(env);
+        let mut net = amount;
+        for rule in rules.iter() {
+            let cut = match rule.amount {
+                WithholdingAmount::Bps(bps) => amount * i128::from(bps) / BPS_DENOMINATOR,
+                WithholdingAmount::Fixed(fixed) => fixed,
+            }
+            .min(net);
+            if cut > 0 {
+                Self::vault_payout(env, &rule.destination, &stream.token, cut);
+                net -= cut;
+                deductions.push_back(Deduction {
+                    label: rule.label,
+                    destination: rule.destination,
+                    amount: cut,
+                });
+            }
+        }
+
+        if let Some((delegate, fee)) = relayer_fee {
+            let fee = fee.min(net);
+            if fee > 0 {
+                Self::vault_payout(env, &delegate, &stream.token, fee);
+                net -= fee;
+                #[allow(deprecated)]
+                env.events().publish(
+                    (
+                        Symbol::new(env, "stream"),
+                        Symbol::new(env, "relayer_fee"),
+                        stream_id,
+                        delegate,
+                    ),
+                    (fee, stream.token.clone()),
+                );
+            }
+        }
+        if net > 0 {
+            Self::pay_worker(env, &stream.worker, &stream.token, net);
+        }
+        if !rules.is_empty() {
+            #[allow(deprecated)]
+            env.events().publish(
+                (
+                    Symbol::new(env, "stream"),
+                    Symbol::new(env, "payslip"),
+                    stream_id,
+                    stream.worker.clone(),
+                ),
+                (amount, deductions, net),
+            );
+        }
+        Self::update_employer_stats(env, &stream.employer, &stream.token, |stats| {
+            stats.total_withdrawn += amount;
+        });
+        Self::update_worker_stats(env, &stream.worker, |stats| {
+            stats.total_earned += amount;
+        });
+        if let Some(team_id) = Self::get_stream_team(env.clone(), stream_id) {
+            Self::registry_team_call(
+                env,
+                "team_stream_paid",
+                vec![env, team_id.into_val(env), amount.into_val(env)],
+            );
+        }
+        amount
+    }
+
+    /// Converts a fiat stream's reference `amount` into its payout token at the
+    /// oracle price and draws it from the stream's reserve, reserving any shortfall
+    /// from the treasury first. With `price_fallback`, an unusable oracle price
+    /// settles from the reserve alone instead of reverting (see `fallback_payout`).
+    fn draw_fiat_reserve(
+        env: &Env,
+        stream_id: u64,
+        stream: &Stream,
+        mut terms: FiatTerms,
+        amount: i128,
+        price_fallback: bool,
+    ) -> i128 {
+        let paid = match Self::checked_quote(env, &terms.currency, &stream.token) {
+            Ok(quote) => Self::to_token(amount, quote),
+            Err(_) if price_fallback => {
+                let paid = Self::fallback_payout(env, stream, &terms, amount);
+                #[allow(deprecated)]
+                env.events().publish(
+                    (
+                        Symbol::new(env, "stream"),
+                        Symbol::new(env, "price_fallback"),
+                        stream_id,
+                    ),
+                    (amount, paid),
+                );
+                paid
+            }
+            Err(reason) => panic!("{}", reason),
+        };
+        if paid > terms.reserve {
+            let shortfall = paid - terms.reserve;
+            Self::vault_call(env, "add_liability", &stream.token, shortfall);
+            Self::update_employer_stats(env, &stream.employer, &stream.token, |stats| {
+                stats.total_committed += shortfall;
+            });
+            terms.reserve = paid;
+        }
+        terms.reserve -= paid;
+        env.storage()
+            .persistent()
+            .set(&StreamKey::FiatTerms(stream_id), &terms);
+        paid
+    }
+
+    /// `amount` converted at the last accepted price, or at the creation price once
+    /// the anchor was reset, and capped at the stream's reserve so an unverified
+    /// price never adds liability.
+    fn fallback_payout(env: &Env, stream: &Stream, terms: &FiatTerms, amount: i128) -> i128 {
+        let anchor: Option<PriceAnchor> = env
+            .storage()
+            .persistent()
+            .get(&StreamKey::PriceAnchor(terms.currency.clone(), stream.token.clone()));
+        let converted = match anchor {
+            Some(anchor) => Self::to_token(amount, (anchor.last_price, anchor.scale)),
+            None => amount.checked_mul(terms.token_rate).expect("amount overflow") / stream.rate,
+        };
+        converted.min(terms.reserve)
+    }
+
+    /// Current price of `token` in `currency` as `(price, scale)`; panics on any
+    /// price `checked_quote` rejects.
+    fn quote(env: &Env, currency: &Symbol, token: &Address) -> (i128, i128) {
+        match Self::checked_quote(env, currency, token) {
+            Ok(quote) => quote,
+            Err(reason) => panic!("{}", reason),
+        }
+    }
+
+    /// Current price of `token` in `currency` as `(price, scale)`, rejecting stale
+    /// quotes and prices beyond the deviation limit from the reference price.
+    fn checked_quote(env: &Env, currency: &Symbol, token: &Address) -> Result<(i128, i128), &'static str> {
+        let config = Self::get_price_oracle(env.clone(), currency.clone())
+            .ok_or("price oracle not configured")?;
+        let oracle = PriceOracleClient::new(env, &config.oracle);
+        let data = match oracle.try_lastprice(&Asset::Stellar(token.clone())) {
+            Ok(Ok(Some(data))) => data,
+            _ => return Err("price unavailable"),
+        };
+        if data.price <= 0 {
+            return Err("invalid price");
+        }
+        let now = env.ledger().timestamp();
+        if now.saturating_sub(data.timestamp) > config.max_age_secs {
+            return Err("stale price");
+        }
+        let scale = match oracle.try_decimals() {
+            Ok(Ok(decimals)) => 10i128.checked_pow(decimals).ok_or("invalid oracle decimals")?,
+            _ => return Err("invalid oracle decimals"),
+        };
+
+        // Checked against a reference that only rolls forward once per period, so
+        // a run of small accepted steps cannot walk the price arbitrarily far
+        let anchor_key = StreamKey::PriceAnchor(currency.clone(), token.clone());
+        let mut anchor = match env.storage().persistent().get::<StreamKey, PriceAnchor>(&anchor_key) {
+            Some(anchor) => {
+                let deviation = (data.price - anchor.reference).abs() * BPS_DENOMINATOR / anchor.reference;
+                if deviation > i128::from(config.max_deviation_bps) {
+                    return Err("price deviation too large");
+                }
+                anchor
+            }
+            None => PriceAnchor {
+                reference: data.price,
+                set_at: now,
+                last_price: data.price,
+                scale,
+            },
+        };
+        if now >= anchor.set_at.saturating_add(PRICE_ANCHOR_PERIOD_SECS) {
+            anchor.reference = data.price;
+            anchor.set_at = now;
+        }
+        anchor.last_price = data.price;
+        anchor.scale = scale;
+        env.storage().persistent().set(&anchor_key, &anchor);
+
+        Ok((data.price, scale))
+    }
+
+    /// Converts a reference currency amount into payout token units.
+    fn to_token(amount: i128, (price, scale): (i128, i128)) -> i128 {
+        amount.checked_mul(scale).expect("amount overflow") / price
+    }
+
+    /// Per-second rate in the payout token, as tracked by the vault and stats.
+    fn token_rate(env: &Env, stream_id: u64, stream: &Stream) -> i128 {
+        Self::get_fiat_terms(env.clone(), stream_id).map_or(stream.rate, |terms| terms.token_rate)
+    }
+
+    /// Removes a stream's rate from the vault outflow and the burn-rate stats.
+    fn release_rate(env: &Env, stream_id: u64, stream: &Stream) {
+        let rate = Self::token_rate(env, stream_id, stream);
+        Self::vault_call(env, "remove_outflow_rate", &stream.token, rate);
+        Self::update_employer_stats(env, &stream.employer, &stream.token, |stats| {
+            stats.burn_rate -= rate;
+        });
+        Self::update_worker_stats(env, &stream.worker, |stats| {
+            stats.current_rate -= rate;
+        });
+    }
+
+    /// Releases a closed stream's outflow rate in the vault and its tracking in the registry.
+    fn release(env: &Env, stream_id: u64, stream: &Stream) {
+        let flag = StreamKey::RateReleased(stream_id);
+        if env.storage().persistent().has(&flag) {
+            env.storage().persistent().remove(&flag);
+        } else {
+            Self::release_rate(env, stream_id, stream);
+        }
+        Self::update_employer_stats(env, &stream.employer, &stream.token, |stats| {
+            stats.active_streams = stats.active_streams.saturating_sub(1);
+        });
+        Self::update_worker_stats(env, &stream.worker, |stats| {
+            stats.active_streams = stats.active_streams.saturating_sub(1);
+        });
+        // Whatever the price buffer did not absorb goes back to the treasury
+        if let Some(mut terms) = Self::get_fiat_terms(env.clone(), stream_id)
+            && terms.reserve > 0
+        {
+            let leftover = terms.reserve;
+            Self::vault_call(env, "remove_liability", &stream.token, leftover);
+            Self::update_employer_stats(env, &stream.employer, &stream.token, |stats| {
+                stats.total_committed -= leftover;
+            });
+            terms.reserve = 0;
+            env.storage()
+                .persistent()
+                .set(&StreamKey::FiatTerms(stream_id), &terms);
+        }
+        Self::registry_notify(env, "stream_closed", &stream.employer, &stream.worker);
+        if let Some(team_id) = Self::get_stream_team(env.clone(), stream_id) {
+            Self::registry_team_call(
+                env,
+                "team_stream_closed",
+                vec![env, team_id.into_val(env), stream.rate.into_val(env)],
+            );
+        }
+    }
+
+    fn update_employer_stats(
+        env: &Env,
+        employer: &Address,
+        token: &Address,
+        update: impl FnOnce(&mut EmployerStats),
+    ) {
+        let key = StreamKey::EmployerStats(employer.clone(), token.clone());
+        let mut stats: EmployerStats = env.storage().persistent().get(&key).unwrap_or_default();
+        update(&mut stats);
+        env.storage().persistent().set(&key, &stats);
+    }
+
+    fn update_worker_stats(env: &Env, worker: &Address, update: impl FnOnce(&mut WorkerStats)) {
+        let key = StreamKey::WorkerStats(worker.clone());
+        let mut stats: WorkerStats = env.storage().persistent().get(&key).unwrap_or_default();
+        update(&mut stats);
+        env.storage().persistent().set(&key, &stats);
+    }
+
+    fn registry_team_call(env: &Env, func: &str, args: Vec<soroban_sdk::Val>) {
+        let registry: Address = env
+            .storage()
+            .instance()
+            .get(&DataKey::Registry)
+            .expect("registry not configured");
+        env.invoke_contract::<()>(&registry, &Symbol::new(env, func), args);
+    }
+
+    /// Tells the linked registry, if any, that a stream between `employer` and `worker` opened or closed.
+    fn registry_notify(env: &Env, func: &str, employer: &Address, worker: &Address) {
+        let registry: Option<Address> = env.storage().instance().get(&DataKey::Registry);
+        if let Some(registry) = registry {
+            env.invoke_contract::<()>(
+                &registry,
+                &Symbol::new(env, func),
+                vec![
+                    env,
+                    employer.clone().into_val(env),
+                    worker.clone().into_val(env),
+                ],
+            );
+        }
+    }
+
+    /// Whether `worker` is registered in the linked registry; false without one.
+    fn is_registered_worker(env: &Env, worker: &Address) -> bool {
+        let registry: Option<Address> = env.storage().instance().get(&DataKey::Registry);
+        match registry {
+            Some(registry) => env.invoke_contract(
+                &registry,
+                &Symbol::new(env, "is_registered"),
+                vec![env, worker.clone().into_val(env)],
+            ),
+            None => false,
+        }
+    }
+
+    fn is_closed(stream: &Stream) -> bool {
+        stream.status == StreamStatus::Canceled || stream.status == StreamStatus::Completed
+    }
+
+    fn close_stream_internal(stream: &mut Stream, now: u64, status: StreamStatus) {
+        stream.status = status;
+        stream.closed_at = now;
+    }
+
+    fn index_slots(env: &Env, index: &StreamIndex) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&StreamKey::IndexSlots(index.clone()))
+            .unwrap_or(0)
+    }
+
+    /// Moves a legacy `Vec<u64>` index into slots, keeping its order.
+    fn index_migrate(env: &Env, index: &StreamIndex) {
+        let legacy_key = match index {
+            StreamIndex::Employer(employer) => StreamKey::EmployerStreams(employer.clone()),
+            StreamIndex::Worker(worker) => StreamKey::WorkerStreams(worker.clone()),
+        };
+        let legacy: Option<Vec<u64>> = env.storage().persistent().get(&legacy_key);
+        if let Some(ids) = legacy {
+            env.storage().persistent().remove(&legacy_key);
+            for stream_id in ids.iter() {
+                Self::index_push(env, index, stream_id);
+            }
+        }
+    }
+
+    fn index_push(env: &Env, index: &StreamIndex, stream_id: u64) {
+        Self::index_migrate(env, index);
+        let slot_key = StreamKey::IndexSlot(index.clone(), stream_id);
+        if env.storage().persistent().has(&slot_key) {
+            return;
+        }
+        let slot = Self::index_slots(env, index);
+        env.storage()
+            .persistent()
+            .set(&StreamKey::IndexEntry(index.clone(), slot), &stream_id);
+        env.storage().persistent().set(&slot_key, &slot);
+        env.storage()
+            .persistent()
+            .set(&StreamKey::IndexSlots(index.clone()), &(slot + 1));
+    }
+
+    /// Empties the slot holding `stream_id`; later slots keep their positions so
+    /// outstanding cursors stay valid.
+    fn index_remove(env: &Env, index: &StreamIndex, stream_id: u64) {
+        Self::index_migrate(env, index);
+        let slot_key = StreamKey::IndexSlot(index.clone(), stream_id);
+        let slot: u32 = match env.storage().persistent().get(&slot_key) {
+            Some(slot) => slot,
+            None => return,
+        };
+        env.storage()
+            .persistent()
+            .remove(&StreamKey::IndexEntry(index.clone(), slot));
+        env.storage().persistent().remove(&slot_key);
+    }
+
+    /// Stream ids held in slots `[start, start + limit)`, skipping emptied slots.
+    fn index_ids(env: &Env, index: &StreamIndex, start: u32, limit: u32) -> Vec<u64> {
+        Self::index_migrate(env, index);
+        let end = start
+            .saturating_add(limit)
+            .min(Self::index_slots(env, index));
+        let mut ids: Vec<u64> = Vec::new(env);
+        let mut slot = start;
+        while slot < end {
+            let entry: Option<u64> = env
+                .storage()
+                .persistent()
+                .get(&StreamKey::IndexEntry(index.clone(), slot));
+            if let Some(stream_id) = entry {
+                ids.push_back(stream_id);
+            }
+            slot += 1;
+        }
+        ids
+    }
+
+    fn index_page(
+        env: &Env,
+        index: &StreamIndex,
+        cursor: u32,
+        limit: u32,
+        status_filter: Option<StreamStatus>,
+    ) -> StreamPage {
+        Self::index_migrate(env, index);
+        // A zero limit would never advance the cursor
+        let limit = limit.max(1);
+        let slots = Self::index_slots(env, index);
+        let scan_end = cursor.saturating_add(MAX_PAGE_SCAN).min(slots);
+        let mut streams: Vec<StreamSummary> = Vec::new(env);
+
+        let mut slot = cursor;
+        while slot < scan_end && streams.len() < limit {
+            let entry: Option<u64> = env
+                .storage()
+                .persistent()
+                .get(&StreamKey::IndexEntry(index.clone(), slot));
+            if let Some(stream_id) = entry
+                && let Some(stream) = Self::get_stream(env.clone(), stream_id)
+                && status_filter.is_none_or(|status| status == stream.status)
+            {
+                streams.push_back(StreamSummary { stream_id, stream });
+            }
+            slot += 1;
+        }
+
+        StreamPage {
+            streams,
+            next_cursor: if slot < slots { Some(slot) } else { None },
+        }
+    }
+
+    fn vested_amount(stream: &Stream, now: u64) -> i128 {
+        Self::vested_amount_at(stream, now)
+    }
+
+    fn vested_amount_at(stream: &Stream, timestamp: u64) -> i128 {
+        let is_completed = stream.status == StreamStatus::Completed;
+        let is_closed = Self::is_closed(stream);
+
         let effective_ts = if is_closed {
             core::cmp::min(timestamp, stream.closed_at)
         } else {
             timestamp
         };
 
-        if effective_ts <= stream.start_ts {
-        if now < stream.cliff_ts {
+        if effective_ts < stream.cliff_ts {
             return 0;
         }
-        if now <= stream.start_ts {
+        if effective_ts <= stream.start_ts {
             return 0;
         }
 
@@ -0,0 +1,58 @@
+use soroban_sdk::{contractclient, contracttype, Address, Env, Symbol};
+
+/// Price feed used to settle streams denominated in a reference currency.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OracleConfig {
+    /// Contract implementing `PriceOracle`, quoting assets in the reference currency.
+    pub oracle: Address,
+    /// Oldest price accepted, in seconds.
+    pub max_age_secs: u64,
+    /// Largest move from the last accepted price, in basis points.
+    pub max_deviation_bps: u32,
+    /// Extra liability reserved on top of the converted total to absorb price moves.
+    pub buffer_bps: u32,
+}
+
+/// Reference a quote must stay within `max_deviation_bps` of, and the last
+/// price accepted against it.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceAnchor {
+    pub reference: i128,
+    /// When `reference` was set; it rolls to the latest accepted price once a
+    /// full anchor period has passed, bounding how fast it can drift.
+    pub set_at: u64,
+    pub last_price: i128,
+    /// `10^decimals()` of the oracle when `last_price` was read.
+    pub scale: i128,
+}
+
+/// SEP-40 asset identifier.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Asset {
+    Stellar(Address),
+    Other(Symbol),
+}
+
+/// SEP-40 price record.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PriceData {
+    pub price: i128,
+    pub timestamp: u64,
+}
+
+/// Subset of the SEP-40 price feed interface (e.g., Reflector) the stream relies on.
+///
+/// Prices are the value of one unit of `asset` in the oracle's base currency,
+/// scaled by `10^decimals()`.
+#[allow(dead_code)]
+#[contractclient(name = "PriceOracleClient")]
+pub trait PriceOracle {
+    fn decimals(env: Env) -> u32;
+
+    /// Most recent price of `asset`, or `None` if the oracle has none.
+    fn lastprice(env: Env, asset: Asset) -> Option<PriceData>;
+}
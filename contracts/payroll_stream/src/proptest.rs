@@ -11,7 +11,11 @@ mod dummy_vault {
     pub struct DummyVault;
     #[contractimpl]
     impl DummyVault {
-        pub fn add_liability(_env: Env, _token: Address, _amount: i128) {}
+        pub fn add_liability(_env: Env, _caller: Address, _token: Address, _amount: i128) {}
+        pub fn remove_liability(_env: Env, _caller: Address, _token: Address, _amount: i128) {}
+        pub fn payout_liability(_env: Env, _caller: Address, _to: Address, _token: Address, _amount: i128) {}
+        pub fn add_outflow_rate(_env: Env, _caller: Address, _token: Address, _rate: i128) {}
+        pub fn remove_outflow_rate(_env: Env, _caller: Address, _token: Address, _rate: i128) {}
     }
 }
 
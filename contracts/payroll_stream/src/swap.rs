@@ -0,0 +1,36 @@
+use soroban_sdk::{contractclient, contracttype, Address, Env};
+
+/// Swap route used to pay workers in their preferred token.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SwapConfig {
+    /// Adapter contract implementing `SwapAdapter`.
+    pub adapter: Address,
+    /// Maximum shortfall accepted against the adapter's quote, in basis points.
+    /// Swaps also never pay out below the worker's floor in the registry.
+    pub max_slippage_bps: u32,
+}
+
+/// Interface a swap adapter (e.g., a wrapper around a DEX router) must expose.
+///
+/// The stream transfers `amount_in` of `token_in` to the adapter before calling
+/// `swap`, and expects the adapter to send the output directly to `to`.
+#[allow(dead_code)]
+#[contractclient(name = "SwapAdapterClient")]
+pub trait SwapAdapter {
+    /// Expected output for swapping `amount_in` of `token_in` into `token_out`.
+    /// Returns 0 when no route exists.
+    fn quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128;
+
+    /// Swap `amount_in` of `token_in` already held by the adapter and send the
+    /// proceeds to `to`. Must panic if the output would be below `min_amount_out`.
+    /// Returns the amount sent.
+    fn swap(
+        env: Env,
+        token_in: Address,
+        token_out: Address,
+        amount_in: i128,
+        min_amount_out: i128,
+        to: Address,
+    ) -> i128;
+}
@@ -8,7 +8,11 @@ mod dummy_vault {
     pub struct DummyVault;
     #[contractimpl]
     impl DummyVault {
-        pub fn add_liability(_env: Env, _token: Address, _amount: i128) {}
+        pub fn add_liability(_env: Env, _caller: Address, _token: Address, _amount: i128) {}
+        pub fn remove_liability(_env: Env, _caller: Address, _token: Address, _amount: i128) {}
+        pub fn payout_liability(_env: Env, _caller: Address, _to: Address, _token: Address, _amount: i128) {}
+        pub fn add_outflow_rate(_env: Env, _caller: Address, _token: Address, _rate: i128) {}
+        pub fn remove_outflow_rate(_env: Env, _caller: Address, _token: Address, _rate: i128) {}
     }
 }
 
@@ -18,12 +22,86 @@ mod rejecting_vault {
     pub struct RejectingVault;
     #[contractimpl]
     impl RejectingVault {
-        pub fn add_liability(_env: Env, _token: Address, _amount: i128) {
+        pub fn add_outflow_rate(_env: Env, _caller: Address, _token: Address, _rate: i128) {}
+        pub fn add_liability(_env: Env, _caller: Address, _token: Address, _amount: i128) {
             panic!("vault rejected liability");
         }
     }
 }
 
+mod mock_dex {
+    use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env};
+
+    #[contracttype]
+    pub enum Key {
+        Rate(Address, Address),
+        Skew,
+    }
+
+    /// Fixed-rate DEX. `skew_bps` makes executed swaps return less than quoted.
+    #[contract]
+    pub struct MockDex;
+    #[contractimpl]
+    impl MockDex {
+        pub fn set_rate(env: Env, token_in: Address, token_out: Address, rate_bps: i128) {
+            env.storage()
+                .instance()
+                .set(&Key::Rate(token_in, token_out), &rate_bps);
+        }
+        pub fn set_skew(env: Env, skew_bps: i128) {
+            env.storage().instance().set(&Key::Skew, &skew_bps);
+        }
+        pub fn quote(env: Env, token_in: Address, token_out: Address, amount_in: i128) -> i128 {
+            let rate: i128 = env
+                .storage()
+                .instance()
+                .get(&Key::Rate(token_in, token_out))
+                .unwrap_or(0);
+            amount_in * rate / 10_000
+        }
+        pub fn swap(
+            env: Env,
+            token_in: Address,
+            token_out: Address,
+            amount_in: i128,
+            min_amount_out: i128,
+            to: Address,
+        ) -> i128 {
+            let skew: i128 = env.storage().instance().get(&Key::Skew).unwrap_or(0);
+            let quoted = Self::quote(env.clone(), token_in, token_out.clone(), amount_in);
+            let out = quoted * (10_000 - skew) / 10_000;
+            if out < min_amount_out {
+                panic!("slippage exceeded");
+            }
+            token::Client::new(&env, &token_out).transfer(&env.current_contract_address(), &to, &out);
+            out
+        }
+    }
+}
+
+mod mock_oracle {
+    use crate::{Asset, PriceData};
+    use soroban_sdk::{contract, contractimpl, Env};
+
+    /// Single-price SEP-40 feed with two decimals, whatever the asset.
+    #[contract]
+    pub struct MockOracle;
+    #[contractimpl]
+    impl MockOracle {
+        pub fn set_price(env: Env, price: i128, timestamp: u64) {
+            env.storage()
+                .instance()
+                .set(&0u32, &PriceData { price, timestamp });
+        }
+        pub fn decimals(_env: Env) -> u32 {
+            2
+        }
+        pub fn lastprice(env: Env, _asset: Asset) -> Option<PriceData> {
+            env.storage().instance().get(&0u32)
+        }
+    }
+}
+
 fn setup(env: &Env) -> (PayrollStreamClient, Address, Address, Address, Address) {
     let admin = Address::generate(env);
     let employer = Address::generate(env);
@@ -1109,3 +1187,1618 @@ fn test_different_employers_have_independent_indexes() {
     assert_eq!(client.get_worker_streams(&worker1).get(0).unwrap(), id1);
     assert_eq!(client.get_worker_streams(&worker2).get(0).unwrap(), id2);
 }
+
+// ---------------------------------------------------------------------------
+// Vault liability invariants (real PayrollVault)
+// ---------------------------------------------------------------------------
+
+fn setup_with_vault(
+    env: &Env,
+) -> (
+    PayrollStreamClient<'_>,
+    payroll_vault::PayrollVaultClient<'_>,
+    Address,
+    Address,
+) {
+    let admin = Address::generate(env);
+    let employer = Address::generate(env);
+
+    let token_admin = Address::generate(env);
+    let token = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = soroban_sdk::token::StellarAssetClient::new(env, &token);
+
+    let vault_id = env.register(payroll_vault::PayrollVault, ());
+    let vault = payroll_vault::PayrollVaultClient::new(env, &vault_id);
+    vault.initialize(&admin);
+
+    let contract_id = env.register(PayrollStream, ());
+    let client = PayrollStreamClient::new(env, &contract_id);
+    client.init(&admin);
+    client.set_vault(&vault_id);
+    vault.set_authorized_contract(&contract_id);
+
+    token_admin_client.mint(&employer, &1_000_000);
+    vault.deposit(&employer, &token, &1_000_000);
+
+    (client, vault, employer, token)
+}
+
+/// Stream running from time 0 to `end_ts` without a cliff.
+fn terms(token: &Address, rate: i128, end_ts: u64) -> StreamParams {
+    StreamParams {
+        token: token.clone(),
+        rate,
+        cliff_ts: 0,
+        start_ts: 0,
+        end_ts,
+    }
+}
+
+/// Sum of `total_amount - withdrawn_amount` over every still-active stream.
+fn outstanding(client: &PayrollStreamClient, ids: &[u64]) -> i128 {
+    let mut sum = 0i128;
+    for id in ids {
+        if let Some(stream) = client.get_stream(id)
+            && stream.status == StreamStatus::Active
+        {
+            sum += stream.total_amount - stream.withdrawn_amount;
+        }
+    }
+    sum
+}
+
+#[test]
+fn test_vault_liability_matches_outstanding_streams() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let stream_contract = client.address.clone();
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    let w3 = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &w1, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &w2, &token, &20, &0u64, &0u64, &100u64);
+    let s3 = client.create_stream(&employer, &w3, &token, &5, &0u64, &0u64, &200u64);
+    let ids = [s1, s2, s3];
+
+    // Each stream is reserved exactly once
+    assert_eq!(vault.get_total_liability(&token), 1_000 + 2_000 + 1_000);
+    assert_eq!(vault.get_total_liability(&token), outstanding(&client, &ids));
+    assert_eq!(vault.get_source_liability(&stream_contract, &token), 4_000);
+
+    env.ledger().with_mut(|li| { li.timestamp = 30; });
+    assert_eq!(client.withdraw(&s1, &w1), 300);
+    assert_eq!(token_client.balance(&w1), 300);
+    assert_eq!(vault.get_total_liability(&token), outstanding(&client, &ids));
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.cancel_stream(&s2, &employer);
+    // Vested 1000 settled to the worker, unvested 1000 released
+    assert_eq!(token_client.balance(&w2), 1_000);
+    assert_eq!(vault.get_total_liability(&token), outstanding(&client, &ids));
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    let results = client.batch_withdraw(&soroban_sdk::vec![&env, s1], &w1);
+    assert_eq!(results.get(0).unwrap().amount, 700);
+    assert_eq!(vault.get_total_liability(&token), outstanding(&client, &ids));
+
+    env.ledger().with_mut(|li| { li.timestamp = 200; });
+    client.withdraw(&s3, &w3);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert_eq!(vault.get_source_liability(&stream_contract, &token), 0);
+    assert_eq!(vault.get_treasury_balance(&token), 1_000_000 - 1_000 - 1_000 - 1_000);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_vault_rejects_stream_beyond_treasury() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let worker = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    // 600_000 + 600_000 would need 1.2M against a 1M treasury
+    client.create_stream(&employer, &worker, &token, &6_000, &0u64, &0u64, &100u64);
+    let res = client.try_create_stream(&employer, &worker, &token, &6_000, &0u64, &0u64, &100u64);
+    assert!(res.is_err());
+    assert_eq!(vault.get_total_liability(&token), 600_000);
+}
+
+#[test]
+fn test_two_stream_contracts_share_one_vault() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (contractors, vault, employer, token) = setup_with_vault(&env);
+
+    let admin = Address::generate(&env);
+    let employees_id = env.register(PayrollStream, ());
+    let employees = PayrollStreamClient::new(&env, &employees_id);
+    employees.init(&admin);
+    employees.set_vault(&vault.address);
+    vault.add_authorized_contract(&employees_id);
+
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = contractors.create_stream(&employer, &w1, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = employees.create_stream(&employer, &w2, &token, &20, &0u64, &0u64, &100u64);
+
+    assert_eq!(vault.get_source_liability(&contractors.address, &token), 1_000);
+    assert_eq!(vault.get_source_liability(&employees_id, &token), 2_000);
+    assert_eq!(vault.get_total_liability(&token), 3_000);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    contractors.withdraw(&s1, &w1);
+    vault.remove_authorized_contract(&contractors.address);
+
+    // The other deployment keeps working
+    employees.withdraw(&s2, &w2);
+    assert_eq!(vault.get_total_liability(&token), 0);
+}
+
+#[test]
+fn test_vault_tracks_outflow_rate_and_enforces_reserve() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &w1, &token, &2, &0u64, &0u64, &100_000u64);
+    let s2 = client.create_stream(&employer, &w2, &token, &3, &0u64, &0u64, &50_000u64);
+    assert_eq!(vault.get_outflow_rate(&token), 5);
+
+    client.cancel_stream(&s1, &employer);
+    assert_eq!(vault.get_outflow_rate(&token), 3);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50_000; });
+    client.withdraw(&s2, &w2);
+    assert_eq!(vault.get_outflow_rate(&token), 0);
+
+    // Treasury now holds 850_000 with no liability. Require 120% coverage.
+    vault.set_reserve_policy(&token, &12_000u32, &0u32);
+    // 700_000 * 1.2 = 840_000 fits, 710_000 * 1.2 = 852_000 does not
+    assert!(client
+        .try_create_stream(&employer, &w1, &token, &71, &0u64, &50_000u64, &60_000u64)
+        .is_err());
+    client.create_stream(&employer, &w1, &token, &70, &0u64, &50_000u64, &60_000u64);
+    let status = vault.get_reserve_status(&token);
+    assert_eq!(status.liability, 700_000);
+    assert_eq!(status.required_reserve, 840_000);
+    assert_eq!(status.withdrawable, 10_000);
+}
+
+#[test]
+fn test_expired_streams_stop_counting_toward_outflow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let worker = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let ended = client.create_stream(&employer, &worker, &token, &2, &0u64, &0u64, &100u64);
+    let running = client.create_stream(&employer, &worker, &token, &3, &0u64, &0u64, &1_000u64);
+    let ids = soroban_sdk::vec![&env, ended, running];
+
+    // Nothing has ended yet
+    env.ledger().with_mut(|li| { li.timestamp = 99; });
+    assert_eq!(client.expire_streams(&ids), 0);
+    assert_eq!(vault.get_outflow_rate(&token), 5);
+
+    env.ledger().with_mut(|li| { li.timestamp = 200; });
+    assert_eq!(client.expire_streams(&ids), 1);
+    assert_eq!(client.expire_streams(&ids), 0);
+    assert_eq!(vault.get_outflow_rate(&token), 3);
+    assert_eq!(vault.get_runway_projection(&token, &1u32).outflow_rate, 3);
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.burn_rate, 3);
+    assert_eq!(stats.active_streams, 2);
+    assert_eq!(client.get_worker_stats(&worker).current_rate, 3);
+
+    // The unwithdrawn pay is still owed, and closing does not release twice
+    assert_eq!(client.withdraw(&ended, &worker), 200);
+    assert_eq!(vault.get_outflow_rate(&token), 3);
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.burn_rate, 3);
+    assert_eq!(stats.active_streams, 1);
+}
+
+// ---------------------------------------------------------------------------
+// WorkforceRegistry active-worker tracking
+// ---------------------------------------------------------------------------
+
+fn link_registry(
+    env: &Env,
+    client: &PayrollStreamClient,
+    employer: &Address,
+) -> workforce_registry::WorkforceRegistryContractClient<'static> {
+    let registry_id = env.register(workforce_registry::WorkforceRegistryContract, ());
+    let registry = workforce_registry::WorkforceRegistryContractClient::new(env, &registry_id);
+    registry.init(&Address::generate(env));
+    registry.set_stream_contract(&client.address);
+    registry.register_employer(
+        employer,
+        &soroban_sdk::BytesN::from_array(env, &[1u8; 32]),
+        &soroban_sdk::String::from_str(env, "QmEmployer"),
+        &Address::generate(env),
+        &Vec::new(env),
+    );
+    client.set_registry(&registry_id);
+    registry
+}
+
+fn worker_metadata(env: &Env) -> workforce_registry::WorkerMetadata {
+    workforce_registry::WorkerMetadata {
+        content_hash: soroban_sdk::BytesN::from_array(env, &[4u8; 32]),
+        scheme: workforce_registry::StorageScheme::Ipfs,
+        uri: soroban_sdk::String::from_str(env, "ipfs://QmWorker"),
+        schema_version: 1,
+        updated_at: 0,
+    }
+}
+
+fn register_worker(
+    env: &Env,
+    registry: &workforce_registry::WorkforceRegistryContractClient,
+) -> Address {
+    let worker = Address::generate(env);
+    registry.register_worker(&worker, &Address::generate(env), &worker_metadata(env));
+    worker
+}
+
+#[test]
+fn test_registry_tracks_streams_through_cancel() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 2);
+    assert_eq!(registry.get_workers_by_employer(&employer, &0u32, &10u32).len(), 1);
+
+    client.cancel_stream(&s1, &employer);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 1);
+    assert_eq!(registry.get_workers_by_employer(&employer, &0u32, &10u32).len(), 1);
+
+    // Canceling twice must not decrement again
+    client.cancel_stream(&s1, &employer);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 1);
+
+    client.cancel_stream(&s2, &employer);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 0);
+    assert_eq!(registry.get_workers_by_employer(&employer, &0u32, &10u32).len(), 0);
+}
+
+#[test]
+fn test_registry_drops_worker_on_completion() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let w1 = register_worker(&env, &registry);
+    let w2 = register_worker(&env, &registry);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &w1, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &w2, &token, &10, &0u64, &0u64, &100u64);
+    assert_eq!(registry.get_workers_by_employer(&employer, &0u32, &10u32).len(), 2);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    client.withdraw(&s1, &w1);
+    let remaining = registry.get_workers_by_employer(&employer, &0u32, &10u32);
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining.get(0).unwrap().wallet, w2);
+
+    client.batch_withdraw(&soroban_sdk::vec![&env, s2], &w2);
+    assert_eq!(registry.get_workers_by_employer(&employer, &0u32, &10u32).len(), 0);
+}
+
+#[test]
+#[should_panic]
+fn test_registry_rejects_stream_for_unregistered_worker() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    link_registry(&env, &client, &employer);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    client.create_stream(&employer, &Address::generate(&env), &token, &10, &0u64, &0u64, &100u64);
+}
+
+#[test]
+fn test_registry_manager_acts_for_employer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let manager = Address::generate(&env);
+    registry.add_manager(&employer, &employer, &manager);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream_for(&employer, &manager, &worker, &terms(&token, 10, 100));
+    let s2 = client.create_stream_for(&employer, &manager, &worker, &terms(&token, 10, 100));
+    assert_eq!(env.auths()[0].0, manager);
+    assert_eq!(client.get_stream(&s1).unwrap().employer, employer);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.cancel_stream_for(&s1, &employer, &manager);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 1);
+
+    let results = client.settle_employer_streams_for(&employer, &manager, &0u32, &10u32);
+    assert_eq!(results.len(), 2);
+    assert!(!results.get(0).unwrap().success);
+    assert_eq!(results.get(1).unwrap().amount, 500);
+
+    let outsider = Address::generate(&env);
+    assert!(client.try_cancel_stream_for(&s2, &employer, &outsider).is_err());
+    assert!(client.try_settle_employer_streams_for(&employer, &outsider, &0u32, &10u32).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Preferred-token payouts via swap adapter
+// ---------------------------------------------------------------------------
+
+/// Links a registry and mock DEX, and registers a worker preferring a second token
+/// the DEX can pay out at `rate_bps` (0 = no route). The worker accepts swaps
+/// at 19000 bps or better.
+fn setup_swap(
+    env: &Env,
+    rate_bps: i128,
+) -> (
+    PayrollStreamClient<'_>,
+    Address,
+    Address,
+    Address,
+    Address,
+    mock_dex::MockDexClient<'_>,
+) {
+    let (client, _vault, employer, token) = setup_with_vault(env);
+    let registry = link_registry(env, &client, &employer);
+
+    let preferred = env
+        .register_stellar_asset_contract_v2(Address::generate(env))
+        .address();
+    let worker = Address::generate(env);
+    registry.register_worker(&worker, &preferred, &worker_metadata(env));
+    registry.set_swap_floor(&worker, &token, &19_000);
+
+    let dex_id = env.register(mock_dex::MockDex, ());
+    let dex = mock_dex::MockDexClient::new(env, &dex_id);
+    if rate_bps > 0 {
+        dex.set_rate(&token, &preferred, &rate_bps);
+    }
+    soroban_sdk::token::StellarAssetClient::new(env, &preferred).mint(&dex_id, &1_000_000);
+    client.set_swap_adapter(&dex_id, &100u32);
+
+    (client, employer, worker, token, preferred, dex)
+}
+
+#[test]
+fn test_payout_swaps_into_preferred_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, dex) = setup_swap(&env, 20_000);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    assert_eq!(client.withdraw(&id, &worker), 500);
+    assert_eq!(preferred_client.balance(&worker), 1_000);
+    assert_eq!(token_client.balance(&worker), 0);
+    assert_eq!(token_client.balance(&dex.address), 500);
+    assert_eq!(token_client.balance(&client.address), 0);
+
+    // Cancellation settlement is routed the same way
+    env.ledger().with_mut(|li| { li.timestamp = 60; });
+    client.cancel_stream(&id, &employer);
+    assert_eq!(preferred_client.balance(&worker), 1_200);
+    assert_eq!(token_client.balance(&worker), 0);
+}
+
+#[test]
+fn test_payout_falls_back_to_stream_token_without_route() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, _dex) = setup_swap(&env, 0);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.batch_withdraw(&soroban_sdk::vec![&env, id], &worker);
+    assert_eq!(token_client.balance(&worker), 500);
+    assert_eq!(preferred_client.balance(&worker), 0);
+}
+
+#[test]
+fn test_payout_skips_swap_below_worker_floor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, dex) = setup_swap(&env, 20_000);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    // The pool price is pushed below the worker's floor; quote and swap agree,
+    // so only the floor catches it and the payout stays in the stream token
+    dex.set_rate(&token, &preferred, &15_000);
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    assert_eq!(client.withdraw(&id, &worker), 500);
+    assert_eq!(token_client.balance(&worker), 500);
+    assert_eq!(preferred_client.balance(&worker), 0);
+
+    // Back above the floor the swap goes through
+    dex.set_rate(&token, &preferred, &19_500);
+    env.ledger().with_mut(|li| { li.timestamp = 60; });
+    client.withdraw(&id, &worker);
+    assert_eq!(preferred_client.balance(&worker), 195);
+}
+
+#[test]
+fn test_payout_without_worker_floor_is_not_swapped() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, _dex) = setup_swap(&env, 20_000);
+    let registry = workforce_registry::WorkforceRegistryContractClient::new(
+        &env,
+        &client.get_registry().unwrap(),
+    );
+    registry.set_swap_floor(&worker, &token, &0);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.withdraw(&id, &worker);
+    assert_eq!(token_client.balance(&worker), 500);
+    assert_eq!(preferred_client.balance(&worker), 0);
+}
+
+#[test]
+fn test_payout_reverts_when_swap_executes_below_floor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, dex) = setup_swap(&env, 20_000);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+    client.set_swap_adapter(&dex.address, &10_000u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    // The adapter quotes above the floor but executes 10% worse; the floor
+    // holds even with the quote-based slippage check disabled
+    dex.set_skew(&1_000);
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    assert!(client.try_withdraw(&id, &worker).is_err());
+    assert_eq!(client.get_stream(&id).unwrap().withdrawn_amount, 0);
+    assert_eq!(token_client.balance(&worker), 0);
+    assert_eq!(preferred_client.balance(&worker), 0);
+}
+
+#[test]
+#[should_panic(expected = "invalid slippage")]
+fn test_set_swap_adapter_rejects_invalid_slippage() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _employer, _worker, _token, _admin) = setup(&env);
+    client.set_swap_adapter(&Address::generate(&env), &10_001u32);
+}
+
+// ---------------------------------------------------------------------------
+// Split payouts via registry payout routes
+// ---------------------------------------------------------------------------
+
+fn payout_share(recipient: &Address, bps: u32) -> workforce_registry::PayoutShare {
+    workforce_registry::PayoutShare {
+        recipient: recipient.clone(),
+        bps,
+    }
+}
+
+#[test]
+fn test_withdraw_splits_across_payout_route() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+
+    let spending = Address::generate(&env);
+    let savings = Address::generate(&env);
+    let family = Address::generate(&env);
+    registry.set_payout_route(
+        &worker,
+        &soroban_sdk::vec![
+            &env,
+            payout_share(&spending, 7_000),
+            payout_share(&savings, 2_000),
+            payout_share(&family, 1_000)
+        ],
+    );
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    assert_eq!(client.withdraw(&id, &worker), 500);
+    assert_eq!(token_client.balance(&spending), 350);
+    assert_eq!(token_client.balance(&savings), 100);
+    assert_eq!(token_client.balance(&family), 50);
+    assert_eq!(token_client.balance(&worker), 0);
+}
+
+#[test]
+fn test_split_payout_gives_rounding_dust_to_last_share() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+
+    let a = Address::generate(&env);
+    let b = Address::generate(&env);
+    let c = Address::generate(&env);
+    registry.set_payout_route(
+        &worker,
+        &soroban_sdk::vec![
+            &env,
+            payout_share(&a, 3_333),
+            payout_share(&b, 3_333),
+            payout_share(&c, 3_334)
+        ],
+    );
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &1, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 10; });
+    client.batch_withdraw(&soroban_sdk::vec![&env, id], &worker);
+    assert_eq!(token_client.balance(&a), 3);
+    assert_eq!(token_client.balance(&b), 3);
+    assert_eq!(token_client.balance(&c), 4);
+}
+
+#[test]
+fn test_split_payout_after_swap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, preferred, _dex) = setup_swap(&env, 20_000);
+    let registry = workforce_registry::WorkforceRegistryContractClient::new(
+        &env,
+        &client.get_registry().unwrap(),
+    );
+    let preferred_client = soroban_sdk::token::Client::new(&env, &preferred);
+
+    let savings = Address::generate(&env);
+    registry.set_payout_route(
+        &worker,
+        &soroban_sdk::vec![&env, payout_share(&worker, 6_000), payout_share(&savings, 4_000)],
+    );
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.withdraw(&id, &worker);
+    assert_eq!(preferred_client.balance(&worker), 600);
+    assert_eq!(preferred_client.balance(&savings), 400);
+    assert_eq!(preferred_client.balance(&client.address), 0);
+}
+
+// ---------------------------------------------------------------------------
+// Worker account recovery
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_guardian_recovery_migrates_streams() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let new_wallet = Address::generate(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    client.cancel_stream(&s2, &employer);
+
+    registry.set_guardian(&worker, &Address::generate(&env));
+    registry.recover_worker(&worker, &new_wallet);
+
+    assert_eq!(client.get_stream(&s1).unwrap().worker, new_wallet);
+    assert_eq!(client.get_stream(&s2).unwrap().worker, new_wallet);
+    assert_eq!(client.get_worker_streams(&worker).len(), 0);
+    assert_eq!(client.get_worker_streams(&new_wallet).len(), 2);
+
+    // Only the live stream moves in the registry's tracking
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 0);
+    assert_eq!(registry.get_active_stream_count(&employer, &new_wallet), 1);
+    let active = registry.get_workers_by_employer(&employer, &0u32, &10u32);
+    assert_eq!(active.len(), 1);
+    assert_eq!(active.get(0).unwrap().wallet, new_wallet);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    assert!(client.try_withdraw(&s1, &worker).is_err());
+    assert_eq!(client.withdraw(&s1, &new_wallet), 500);
+    assert_eq!(token_client.balance(&new_wallet), 500);
+
+    // Finishing the migrated stream clears the new wallet's tracking
+    client.cancel_stream(&s1, &employer);
+    assert_eq!(registry.get_live_stream_count(&new_wallet), 0);
+    registry.deregister_worker(&new_wallet);
+}
+
+#[test]
+fn test_employer_recovery_after_timelock() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let new_wallet = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id = client.create_stream(&employer, &worker, &token, &1, &0u64, &0u64, &(8 * 86_400u64));
+
+    registry.initiate_recovery(&employer, &worker, &new_wallet);
+    assert!(registry.try_execute_recovery(&worker).is_err());
+
+    env.ledger().with_mut(|li| { li.timestamp = 7 * 86_400; });
+    registry.execute_recovery(&worker);
+
+    assert_eq!(client.get_stream(&id).unwrap().worker, new_wallet);
+    assert!(registry.is_registered(&new_wallet));
+    assert!(!registry.is_registered(&worker));
+    assert!(registry.try_deregister_worker(&new_wallet).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Attestation requirements
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_create_stream_requires_attestations() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let issuer = Address::generate(&env);
+    let kyc = Symbol::new(&env, "KYC");
+    registry.add_issuer(&issuer);
+
+    client.set_required_attestations(&employer, &soroban_sdk::vec![&env, kyc.clone()]);
+    assert_eq!(client.get_required_attestations(&employer).len(), 1);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    assert!(client
+        .try_create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64)
+        .is_err());
+
+    registry.attest(
+        &issuer,
+        &worker,
+        &kyc,
+        &soroban_sdk::BytesN::from_array(&env, &[5u8; 32]),
+        &1_000,
+    );
+    client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    // Once the attestation expires new streams are refused again
+    env.ledger().with_mut(|li| { li.timestamp = 1_000; });
+    assert!(client
+        .try_create_stream(&employer, &worker, &token, &10, &1_000u64, &1_000u64, &1_100u64)
+        .is_err());
+
+    client.set_required_attestations(&employer, &Vec::new(&env));
+    client.create_stream(&employer, &worker, &token, &10, &1_000u64, &1_000u64, &1_100u64);
+}
+
+// ---------------------------------------------------------------------------
+// Team-tagged streams
+// ---------------------------------------------------------------------------
+
+const MONTH: i128 = 30 * 86_400;
+
+#[test]
+fn test_team_stream_tracks_and_caps_spend() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let w1 = register_worker(&env, &registry);
+    let w2 = register_worker(&env, &registry);
+    let outsider = register_worker(&env, &registry);
+
+    let team_id = registry.create_team(
+        &employer,
+        &employer,
+        &soroban_sdk::String::from_str(&env, "Design"),
+        &employer,
+        &token,
+        &(15 * MONTH),
+    );
+    registry.add_team_member(&employer, &team_id, &w1);
+    registry.add_team_member(&employer, &team_id, &w2);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_team_stream(&employer, &w1, &terms(&token, 10, 100), &team_id);
+    assert_eq!(client.get_stream_team(&s1), Some(team_id));
+    let spend = registry.get_team_spend(&team_id);
+    assert_eq!(spend.active_streams, 1);
+    assert_eq!(spend.committed_rate, 10);
+    assert_eq!(spend.monthly_burn, 10 * MONTH);
+
+    // A second stream would push the burn past the cap
+    assert!(client
+        .try_create_team_stream(&employer, &w2, &terms(&token, 10, 100), &team_id)
+        .is_err());
+    // Non-members and foreign tokens are rejected outright
+    assert!(client
+        .try_create_team_stream(&employer, &outsider, &terms(&token, 1, 100), &team_id)
+        .is_err());
+    assert!(client
+        .try_create_team_stream(&employer, &w2, &terms(&Address::generate(&env), 1, 100), &team_id)
+        .is_err());
+    let s2 = client.create_team_stream(&employer, &w2, &terms(&token, 5, 100), &team_id);
+
+    env.ledger().with_mut(|li| { li.timestamp = 40; });
+    client.withdraw(&s1, &w1);
+    client.cancel_stream(&s2, &employer);
+    let spend = registry.get_team_spend(&team_id);
+    assert_eq!(spend.total_paid, 400 + 200);
+    assert_eq!(spend.active_streams, 1);
+    assert_eq!(spend.committed_rate, 10);
+
+    // Completion frees the budget
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    client.withdraw(&s1, &w1);
+    let spend = registry.get_team_spend(&team_id);
+    assert_eq!(spend.active_streams, 0);
+    assert_eq!(spend.monthly_burn, 0);
+    assert_eq!(spend.total_paid, 1_000 + 200);
+
+    // Untagged streams do not count towards any team
+    let s3 = client.create_stream(&employer, &w2, &token, &10, &100u64, &100u64, &200u64);
+    assert_eq!(client.get_stream_team(&s3), None);
+    assert_eq!(registry.get_team_spend(&team_id).active_streams, 0);
+}
+
+// ---------------------------------------------------------------------------
+// Withdrawal delegation
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_delegate_withdraws_for_worker_with_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+    let relayer = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &worker, &token, &5, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 30; });
+    // Not yet authorized
+    assert!(client.try_withdraw_for(&s1, &worker, &relayer, &0).is_err());
+
+    client.set_withdraw_delegate(&worker, &relayer, &20);
+    assert_eq!(client.get_withdraw_delegate(&worker, &relayer), Some(20));
+    // Fee above the cap is rejected
+    assert!(client.try_withdraw_for(&s1, &worker, &relayer, &21).is_err());
+
+    assert_eq!(client.withdraw_for(&s1, &worker, &relayer, &20), 300);
+    assert_eq!(token_client.balance(&worker), 280);
+    assert_eq!(token_client.balance(&relayer), 20);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    let results = client.batch_withdraw_for(&soroban_sdk::vec![&env, s1, s2], &worker, &relayer, &10);
+    assert_eq!(results.get(0).unwrap().amount, 700);
+    assert_eq!(results.get(1).unwrap().amount, 500);
+    assert_eq!(token_client.balance(&worker), 280 + 690 + 490);
+    assert_eq!(token_client.balance(&relayer), 20 + 10 + 10);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_revoked_delegate_cannot_withdraw() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, _admin) = setup(&env);
+    let keeper = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    client.set_withdraw_delegate(&worker, &keeper, &0);
+
+    env.ledger().with_mut(|li| { li.timestamp = 10; });
+    assert_eq!(client.withdraw_for(&stream_id, &worker, &keeper, &0), 100);
+
+    client.revoke_withdraw_delegate(&worker, &keeper);
+    assert_eq!(client.get_withdraw_delegate(&worker, &keeper), None);
+    env.ledger().with_mut(|li| { li.timestamp = 20; });
+    assert!(client.try_withdraw_for(&stream_id, &worker, &keeper, &0).is_err());
+    assert!(client
+        .try_batch_withdraw_for(&soroban_sdk::vec![&env, stream_id], &worker, &keeper, &0)
+        .is_err());
+
+    // A delegate of one worker cannot touch another worker's stream
+    let other = Address::generate(&env);
+    client.set_withdraw_delegate(&other, &keeper, &0);
+    assert!(client.try_withdraw_for(&stream_id, &other, &keeper, &0).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Employer payroll runs
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_settle_employer_streams_pays_each_worker() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    let w3 = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &w1, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &w2, &token, &20, &0u64, &0u64, &100u64);
+    let s3 = client.create_stream(&employer, &w3, &token, &5, &0u64, &0u64, &200u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.cancel_stream(&s2, &employer);
+
+    let results = client.settle_employer_streams(&employer, &0u32, &10u32);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results.get(0).unwrap().amount, 500);
+    assert!(!results.get(1).unwrap().success);
+    assert_eq!(results.get(2).unwrap().amount, 250);
+    assert_eq!(token_client.balance(&w1), 500);
+    assert_eq!(token_client.balance(&w2), 1_000);
+    assert_eq!(token_client.balance(&w3), 250);
+
+    // Pages only cover the requested window
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    let results = client.settle_employer_streams(&employer, &2u32, &5u32);
+    assert_eq!(results.len(), 1);
+    assert_eq!(results.get(0).unwrap().stream_id, s3);
+    assert_eq!(token_client.balance(&w3), 500);
+    assert_eq!(client.get_stream(&s1).unwrap().withdrawn_amount, 500);
+    assert_eq!(client.settle_employer_streams(&employer, &3u32, &5u32).len(), 0);
+
+    let results = client.settle_employer_streams(&employer, &0u32, &1u32);
+    assert_eq!(results.get(0).unwrap().amount, 500);
+    assert_eq!(client.get_stream(&s1).unwrap().status, StreamStatus::Completed);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_settle_employer_streams_skips_fiat_stream_without_a_usable_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    let usd = Symbol::new(&env, "USD");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    oracle.set_price(&100, &0);
+    client.set_price_oracle(&usd, &oracle_id, &60u64, &1_000u32, &1_000u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let fiat = client.create_fiat_stream(&employer, &w1, &terms(&token, 1, 100), &usd);
+    client.create_stream(&employer, &w2, &token, &1, &0u64, &0u64, &100u64);
+
+    // A price far outside the deviation band fails only its own stream, and an
+    // oversized limit is capped rather than scanning unbounded
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    oracle.set_price(&300, &50);
+    let results = client.settle_employer_streams(&employer, &0u32, &u32::MAX);
+    assert_eq!(results.len(), 2);
+    assert!(!results.get(0).unwrap().success);
+    assert_eq!(results.get(0).unwrap().stream_id, fiat);
+    assert_eq!(results.get(1).unwrap().amount, 50);
+    assert_eq!(token_client.balance(&w1), 0);
+    assert_eq!(token_client.balance(&w2), 50);
+}
+
+// ---------------------------------------------------------------------------
+// Paged stream indexes
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_employer_streams_page_filters_and_resumes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, _admin) = setup(&env);
+    client.set_retention_secs(&0u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let mut ids = [0u64; 5];
+    for (i, id) in ids.iter_mut().enumerate() {
+        *id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &(100 + i as u64));
+    }
+    client.cancel_stream(&ids[1], &employer);
+    client.cancel_stream(&ids[3], &employer);
+
+    let page = client.get_employer_streams_page(&employer, &0u32, &2u32, &None);
+    assert_eq!(page.streams.len(), 2);
+    assert_eq!(page.streams.get(0).unwrap().stream_id, ids[0]);
+    assert_eq!(page.next_cursor, Some(2));
+    let page = client.get_employer_streams_page(&employer, &2u32, &10u32, &None);
+    assert_eq!(page.streams.len(), 3);
+    assert_eq!(page.next_cursor, None);
+
+    let active = client.get_employer_streams_page(&employer, &0u32, &10u32, &Some(StreamStatus::Active));
+    assert_eq!(active.streams.len(), 3);
+    assert_eq!(active.streams.get(1).unwrap().stream_id, ids[2]);
+    let canceled = client.get_worker_streams_page(&worker, &0u32, &10u32, &Some(StreamStatus::Canceled));
+    assert_eq!(canceled.streams.len(), 2);
+    assert_eq!(canceled.streams.get(1).unwrap().stream.status, StreamStatus::Canceled);
+
+    // Cleaning up a stream leaves later cursors pointing at the same streams
+    client.cleanup_stream(&ids[1]);
+    let page = client.get_employer_streams_page(&employer, &2u32, &1u32, &None);
+    assert_eq!(page.streams.get(0).unwrap().stream_id, ids[2]);
+    assert_eq!(page.next_cursor, Some(3));
+    assert_eq!(client.get_employer_streams(&employer).len(), 4);
+    assert_eq!(client.get_employer_streams_page(&employer, &3u32, &100u32, &None).streams.len(), 2);
+
+    // A zero limit still advances, so callers following the cursor terminate
+    let page = client.get_employer_streams_page(&employer, &0u32, &0u32, &None);
+    assert_eq!(page.streams.len(), 1);
+    assert_eq!(page.next_cursor, Some(1));
+}
+
+#[test]
+fn test_recovery_of_many_streams_resumes_in_pages() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.cost_estimate().budget().reset_unlimited();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let new_wallet = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let mut last = 0;
+    for _ in 0..105 {
+        last = client.create_stream(&employer, &worker, &token, &1, &0u64, &0u64, &100u64);
+    }
+
+    registry.set_guardian(&worker, &Address::generate(&env));
+    registry.recover_worker(&worker, &new_wallet);
+    assert_eq!(client.get_worker_streams(&new_wallet).len(), 100);
+    assert_eq!(client.get_stream(&last).unwrap().worker, worker);
+    assert_eq!(registry.get_active_stream_count(&employer, &new_wallet), 100);
+
+    assert!(!registry.resume_stream_migration(&worker));
+    assert_eq!(client.get_stream(&last).unwrap().worker, new_wallet);
+    assert_eq!(client.get_worker_streams_page(&new_wallet, &100u32, &100u32, &None).streams.len(), 5);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 0);
+    assert_eq!(registry.get_active_stream_count(&employer, &new_wallet), 105);
+    assert!(registry.try_resume_stream_migration(&worker).is_err());
+}
+
+#[test]
+fn test_legacy_stream_index_is_migrated() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, _admin) = setup(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let id1 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    // Simulate an index written before streams were paged
+    env.as_contract(&client.address, || {
+        let ids = soroban_sdk::vec![&env, 39u64, 40u64, 41u64];
+        env.storage()
+            .persistent()
+            .set(&StreamKey::EmployerStreams(employer.clone()), &ids);
+    });
+
+    let id2 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    assert_eq!(
+        client.get_employer_streams(&employer),
+        soroban_sdk::vec![&env, id1, 39u64, 40u64, 41u64, id2]
+    );
+    env.as_contract(&client.address, || {
+        assert!(!env
+            .storage()
+            .persistent()
+            .has(&StreamKey::EmployerStreams(employer.clone())));
+    });
+}
+
+// ---------------------------------------------------------------------------
+// Aggregate analytics
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_stats_track_create_withdraw_cancel_and_cleanup() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, _admin) = setup(&env);
+    let other_token = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    client.set_retention_secs(&0u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    let s2 = client.create_stream(&employer, &w2, &token, &20, &0u64, &0u64, &100u64);
+    client.create_stream(&employer, &worker, &other_token, &3, &0u64, &0u64, &100u64);
+
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.active_streams, 2);
+    assert_eq!(stats.burn_rate, 30);
+    assert_eq!(stats.total_committed, 3_000);
+    assert_eq!(stats.total_withdrawn, 0);
+    assert_eq!(client.get_employer_stats(&employer, &other_token).burn_rate, 3);
+    assert_eq!(client.get_worker_stats(&worker).current_rate, 13);
+    assert_eq!(client.get_worker_stats(&worker).active_streams, 2);
+
+    env.ledger().with_mut(|li| { li.timestamp = 40; });
+    client.withdraw(&s1, &worker);
+    client.cancel_stream(&s2, &employer);
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.active_streams, 1);
+    assert_eq!(stats.burn_rate, 10);
+    assert_eq!(stats.total_committed, 1_000 + 800);
+    assert_eq!(stats.total_withdrawn, 400 + 800);
+    assert_eq!(client.get_worker_stats(&w2).current_rate, 0);
+    assert_eq!(client.get_worker_stats(&w2).total_earned, 800);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    client.withdraw(&s1, &worker);
+    client.cleanup_stream(&s1);
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.active_streams, 0);
+    assert_eq!(stats.burn_rate, 0);
+    assert_eq!(stats.total_withdrawn, stats.total_committed);
+    let worker_stats = client.get_worker_stats(&worker);
+    assert_eq!(worker_stats.total_earned, 1_000);
+    assert_eq!(worker_stats.current_rate, 3);
+    assert_eq!(worker_stats.active_streams, 1);
+}
+
+// ---------------------------------------------------------------------------
+// Stream transfer
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_transfer_stream_moves_entitlement_and_indexes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 30; });
+    client.withdraw(&stream_id, &worker);
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.transfer_stream(&stream_id, &buyer);
+
+    assert_eq!(client.get_stream(&stream_id).unwrap().worker, buyer);
+    assert_eq!(client.get_worker_streams(&worker).len(), 0);
+    assert_eq!(client.get_worker_streams(&buyer), soroban_sdk::vec![&env, stream_id]);
+    assert_eq!(client.get_worker_stats(&worker).current_rate, 0);
+    assert_eq!(client.get_worker_stats(&buyer).current_rate, 10);
+    assert!(client.try_withdraw(&stream_id, &worker).is_err());
+    assert!(client.try_transfer_stream(&stream_id, &buyer).is_err());
+
+    // Accrued-but-unwithdrawn funds move with the stream
+    assert_eq!(client.withdraw(&stream_id, &buyer), 200);
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    assert_eq!(client.withdraw(&stream_id, &buyer), 500);
+    assert_eq!(token_client.balance(&worker), 300);
+    assert_eq!(token_client.balance(&buyer), 700);
+    assert!(client.try_transfer_stream(&stream_id, &worker).is_err());
+}
+
+#[test]
+fn test_transfer_stream_to_unregistered_buyer_with_registry() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let registry = link_registry(&env, &client, &employer);
+    let worker = register_worker(&env, &registry);
+    let buyer = Address::generate(&env);
+    let wallet = register_worker(&env, &registry);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let sold = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    let moved = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+
+    // The buyer is not a registered worker, so the registry does not track it
+    client.transfer_stream(&sold, &buyer);
+    assert_eq!(client.get_stream(&sold).unwrap().worker, buyer);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 1);
+    assert_eq!(registry.get_active_stream_count(&employer, &buyer), 0);
+
+    // Registered recipients are still tracked
+    client.transfer_stream(&moved, &wallet);
+    assert_eq!(registry.get_active_stream_count(&employer, &worker), 0);
+    assert_eq!(registry.get_active_stream_count(&employer, &wallet), 1);
+
+    // Closing the sold stream does not touch the registry's counts
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    assert_eq!(client.withdraw(&sold, &buyer), 1_000);
+    assert_eq!(client.get_stream(&sold).unwrap().status, StreamStatus::Completed);
+    assert_eq!(registry.get_active_stream_count(&employer, &wallet), 1);
+}
+
+#[test]
+fn test_transfer_stream_requires_employer_consent_when_set() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, employer, worker, token, _admin) = setup(&env);
+    let new_wallet = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &100u64);
+    client.set_transfer_consent(&employer, &true);
+    assert!(client.requires_transfer_consent(&employer));
+
+    // Only the worker signs: rejected
+    env.mock_auths(&[soroban_sdk::testutils::MockAuth {
+        address: &worker,
+        invoke: &soroban_sdk::testutils::MockAuthInvoke {
+            contract: &client.address,
+            fn_name: "transfer_stream",
+            args: (stream_id, new_wallet.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(client.try_transfer_stream(&stream_id, &new_wallet).is_err());
+
+    env.mock_all_auths();
+    client.transfer_stream(&stream_id, &new_wallet);
+    assert_eq!(client.get_stream(&stream_id).unwrap().worker, new_wallet);
+}
+
+// ---------------------------------------------------------------------------
+// Salary advances
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_advance_is_repaid_from_future_accrual() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let w1 = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let s1 = client.create_stream(&employer, &w1, &token, &4, &0u64, &0u64, &100_000u64);
+
+    env.ledger().with_mut(|li| { li.timestamp = 1_000; });
+    assert!(client.try_request_advance(&s1, &w1, &1_000).is_err());
+
+    // Half of the next day's accrual, 1% fee
+    client.set_advance_policy(&employer, &5_000u32, &1u32, &100u32);
+    assert!(client.try_request_advance(&s1, &w1, &172_801).is_err());
+
+    // Already-vested 4_000 is settled along with the 40_000 advance
+    assert_eq!(client.request_advance(&s1, &w1, &40_000), 44_000);
+    assert_eq!(token_client.balance(&w1), 44_000);
+    assert_eq!(client.get_advance_outstanding(&s1), 40_400);
+    assert!(client.try_request_advance(&s1, &w1, &1_000).is_err());
+
+    env.ledger().with_mut(|li| { li.timestamp = 5_000; });
+    assert_eq!(client.withdraw(&s1, &w1), 0);
+    assert_eq!(client.get_advance_outstanding(&s1), 24_400);
+
+    env.ledger().with_mut(|li| { li.timestamp = 12_000; });
+    assert_eq!(client.get_advance_outstanding(&s1), 0);
+    assert_eq!(client.withdraw(&s1, &w1), 3_600);
+    assert_eq!(vault.get_total_liability(&token), 400_000 - 48_000);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100_000; });
+    client.withdraw(&s1, &w1);
+    assert_eq!(client.get_stream(&s1).unwrap().status, StreamStatus::Completed);
+    // The worker nets everything but the fee
+    assert_eq!(token_client.balance(&w1), 400_000 - 400);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_cancel_writes_off_unrepaid_advance() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_stream(&employer, &worker, &token, &4, &0u64, &0u64, &100_000u64);
+    client.set_advance_policy(&employer, &5_000u32, &1u32, &100u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 1_000; });
+    client.request_advance(&stream_id, &worker, &40_000);
+
+    env.ledger().with_mut(|li| { li.timestamp = 5_000; });
+    client.cancel_stream(&stream_id, &employer);
+    assert_eq!(token_client.balance(&worker), 44_000);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert_eq!(vault.get_treasury_balance(&token), 1_000_000 - 44_000);
+    assert!(vault.check_invariants(&token));
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.total_withdrawn, 44_000);
+    assert_eq!(stats.total_committed, 44_000);
+}
+
+// ---------------------------------------------------------------------------
+// Withholding
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_withholding_routes_deductions_and_reports_payslip() {
+    use soroban_sdk::testutils::Events as _;
+    use soroban_sdk::TryFromVal;
+
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+    let tax = Address::generate(&env);
+    let pension = Address::generate(&env);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_stream(&employer, &worker, &token, &10, &0u64, &0u64, &200u64);
+    let rules = soroban_sdk::vec![
+        &env,
+        WithholdingRule {
+            destination: tax.clone(),
+            amount: WithholdingAmount::Bps(2_000),
+            label: Symbol::new(&env, "income_tax"),
+        },
+        WithholdingRule {
+            destination: pension.clone(),
+            amount: WithholdingAmount::Fixed(50),
+            label: Symbol::new(&env, "pension"),
+        },
+    ];
+    client.set_withholding(&employer, &stream_id, &rules);
+    assert_eq!(client.get_withholding(&stream_id), rules);
+
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    assert_eq!(client.withdraw(&stream_id, &worker), 1_000);
+    let payslip_topic = Symbol::new(&env, "payslip");
+    let (_, _, data) = env
+        .events()
+        .all()
+        .iter()
+        .find(|(_, topics, _)| {
+            Symbol::try_from_val(&env, &topics.get(1).unwrap()).ok() == Some(payslip_topic.clone())
+        })
+        .unwrap();
+    let (gross, deductions, net) = <(i128, Vec<Deduction>, i128)>::try_from_val(&env, &data).unwrap();
+    assert_eq!(gross, 1_000);
+    assert_eq!(deductions.len(), 2);
+    assert_eq!(deductions.get(0).unwrap().amount, 200);
+    assert_eq!(deductions.get(1).unwrap().label, Symbol::new(&env, "pension"));
+    assert_eq!(net, 750);
+    assert_eq!(token_client.balance(&tax), 200);
+    assert_eq!(token_client.balance(&pension), 50);
+    assert_eq!(token_client.balance(&worker), 750);
+
+    // A fixed deduction never exceeds what is left of the payout
+    env.ledger().with_mut(|li| { li.timestamp = 105; });
+    client.set_withholding(
+        &employer,
+        &stream_id,
+        &soroban_sdk::vec![
+            &env,
+            WithholdingRule {
+                destination: pension.clone(),
+                amount: WithholdingAmount::Fixed(80),
+                label: Symbol::new(&env, "pension"),
+            },
+        ],
+    );
+    client.withdraw(&stream_id, &worker);
+    assert_eq!(token_client.balance(&pension), 100);
+    assert_eq!(token_client.balance(&worker), 750);
+    assert!(vault.check_invariants(&token));
+
+    // Rules are validated and owned by the employer
+    assert!(client
+        .try_set_withholding(&worker, &stream_id, &Vec::new(&env))
+        .is_err());
+    let too_much = soroban_sdk::vec![
+        &env,
+        WithholdingRule {
+            destination: tax.clone(),
+            amount: WithholdingAmount::Bps(10_001),
+            label: Symbol::new(&env, "income_tax"),
+        },
+    ];
+    assert!(client.try_set_withholding(&employer, &stream_id, &too_much).is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Multi-token baskets
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_basket_stream_pays_and_cancels_every_leg() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, usdc) = setup_with_vault(&env);
+    let project = env
+        .register_stellar_asset_contract_v2(Address::generate(&env))
+        .address();
+    soroban_sdk::token::StellarAssetClient::new(&env, &project).mint(&employer, &500_000);
+    vault.deposit(&employer, &project, &500_000);
+    let usdc_client = soroban_sdk::token::Client::new(&env, &usdc);
+    let project_client = soroban_sdk::token::Client::new(&env, &project);
+    let worker = Address::generate(&env);
+
+    let legs = soroban_sdk::vec![
+        &env,
+        StreamLeg { token: usdc.clone(), rate: 10 },
+        StreamLeg { token: project.clone(), rate: 4 },
+    ];
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let basket_id = client.create_basket_stream(&employer, &worker, &legs, &0u64, &0u64, &100u64);
+    let leg_ids = client.get_basket(&basket_id).unwrap();
+    assert_eq!(leg_ids.len(), 2);
+    assert_eq!(client.get_stream_basket(&leg_ids.get(1).unwrap()), Some(basket_id));
+    assert_eq!(vault.get_total_liability(&usdc), 1_000);
+    assert_eq!(vault.get_total_liability(&project), 400);
+
+    env.ledger().with_mut(|li| { li.timestamp = 25; });
+    let results = client.withdraw_basket(&basket_id, &worker);
+    assert_eq!(results.get(0).unwrap().amount, 250);
+    assert_eq!(results.get(1).unwrap().amount, 100);
+    assert_eq!(usdc_client.balance(&worker), 250);
+    assert_eq!(project_client.balance(&worker), 100);
+
+    // Canceling at the halfway point releases half of each leg
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    client.cancel_basket(&basket_id, &employer);
+    assert_eq!(usdc_client.balance(&worker), 500);
+    assert_eq!(project_client.balance(&worker), 200);
+    assert_eq!(vault.get_total_liability(&usdc), 0);
+    assert_eq!(vault.get_total_liability(&project), 0);
+    assert_eq!(vault.get_treasury_balance(&project), 500_000 - 200);
+    assert!(vault.check_invariants(&project));
+
+    // Legs must use distinct tokens
+    let dup = soroban_sdk::vec![
+        &env,
+        StreamLeg { token: usdc.clone(), rate: 1 },
+        StreamLeg { token: usdc.clone(), rate: 2 },
+    ];
+    assert!(client
+        .try_create_basket_stream(&employer, &worker, &dup, &0u64, &50u64, &100u64)
+        .is_err());
+}
+
+// ---------------------------------------------------------------------------
+// Fiat-denominated streams
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_fiat_stream_settles_at_oracle_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+    let usd = Symbol::new(&env, "USD");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    // 1 token = 2.00 USD; quotes up to 60s old, 20% deviation, 10% buffer
+    oracle.set_price(&200, &0);
+    client.set_price_oracle(&usd, &oracle_id, &60u64, &2_000u32, &1_000u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_fiat_stream(&employer, &worker, &terms(&token, 10, 100), &usd);
+    // 1_000 USD is 500 tokens today, plus the buffer
+    assert_eq!(vault.get_total_liability(&token), 550);
+    assert_eq!(client.get_fiat_terms(&stream_id).unwrap().token_rate, 5);
+    assert_eq!(client.get_employer_stats(&employer, &token).burn_rate, 5);
+
+    env.ledger().with_mut(|li| { li.timestamp = 50; });
+    oracle.set_price(&200, &50);
+    assert_eq!(client.withdraw(&stream_id, &worker), 250);
+
+    // The token weakens: the same 300 USD buys more tokens
+    env.ledger().with_mut(|li| { li.timestamp = 80; });
+    oracle.set_price(&180, &80);
+    assert_eq!(client.withdraw(&stream_id, &worker), 166);
+    assert_eq!(client.get_fiat_terms(&stream_id).unwrap().reserve, 134);
+
+    // Stale and sharply moved prices are rejected
+    env.ledger().with_mut(|li| { li.timestamp = 90; });
+    oracle.set_price(&180, &20);
+    assert!(client.try_withdraw(&stream_id, &worker).is_err());
+    oracle.set_price(&100, &90);
+    assert!(client.try_withdraw(&stream_id, &worker).is_err());
+
+    // Once the admin accepts the move, the final payout tops up the reserve
+    client.reset_price_anchor(&usd, &token);
+    env.ledger().with_mut(|li| { li.timestamp = 100; });
+    oracle.set_price(&100, &100);
+    assert_eq!(client.withdraw(&stream_id, &worker), 200);
+    assert_eq!(client.get_stream(&stream_id).unwrap().status, StreamStatus::Completed);
+
+    assert_eq!(token_client.balance(&worker), 616);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert_eq!(vault.get_treasury_balance(&token), 1_000_000 - 616);
+    assert!(vault.check_invariants(&token));
+    let stats = client.get_employer_stats(&employer, &token);
+    assert_eq!(stats.total_committed, 616);
+    assert_eq!(stats.burn_rate, 0);
+}
+
+#[test]
+fn test_canceled_fiat_stream_releases_remaining_reserve() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let worker = Address::generate(&env);
+    let eur = Symbol::new(&env, "EUR");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    oracle.set_price(&50, &0);
+    client.set_price_oracle(&eur, &oracle_id, &60u64, &2_000u32, &500u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_fiat_stream(&employer, &worker, &terms(&token, 1, 100), &eur);
+    assert_eq!(vault.get_total_liability(&token), 210);
+
+    env.ledger().with_mut(|li| { li.timestamp = 40; });
+    oracle.set_price(&50, &40);
+    client.cancel_stream(&stream_id, &employer);
+    assert_eq!(soroban_sdk::token::Client::new(&env, &token).balance(&worker), 80);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert_eq!(client.get_fiat_terms(&stream_id).unwrap().reserve, 0);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_price_reference_rolls_at_most_once_per_day() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let worker = Address::generate(&env);
+    let usd = Symbol::new(&env, "USD");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    oracle.set_price(&100, &0);
+    client.set_price_oracle(&usd, &oracle_id, &60u64, &1_000u32, &1_000u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let stream_id = client.create_fiat_stream(&employer, &worker, &terms(&token, 1, 200_000), &usd);
+
+    // Small steps are each within 10% of the previous price, but not of the reference
+    env.ledger().with_mut(|li| { li.timestamp = 10; });
+    oracle.set_price(&108, &10);
+    client.withdraw(&stream_id, &worker);
+    env.ledger().with_mut(|li| { li.timestamp = 20; });
+    oracle.set_price(&116, &20);
+    assert!(client.try_withdraw(&stream_id, &worker).is_err());
+
+    // A day later the reference rolls to the next accepted price
+    env.ledger().with_mut(|li| { li.timestamp = 86_400; });
+    oracle.set_price(&108, &86_400);
+    client.withdraw(&stream_id, &worker);
+    env.ledger().with_mut(|li| { li.timestamp = 86_410; });
+    oracle.set_price(&116, &86_410);
+    client.withdraw(&stream_id, &worker);
+}
+
+#[test]
+fn test_cancel_settles_from_reserve_without_a_usable_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let eur = Symbol::new(&env, "EUR");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    oracle.set_price(&50, &0);
+    client.set_price_oracle(&eur, &oracle_id, &60u64, &2_000u32, &500u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let w1 = Address::generate(&env);
+    let w2 = Address::generate(&env);
+    let s1 = client.create_fiat_stream(&employer, &w1, &terms(&token, 1, 100), &eur);
+    let s2 = client.create_fiat_stream(&employer, &w2, &terms(&token, 1, 100), &eur);
+    assert_eq!(vault.get_total_liability(&token), 420);
+
+    // The feed goes stale: withdrawals wait, cancellation pays at the last good price
+    env.ledger().with_mut(|li| { li.timestamp = 70; });
+    assert!(client.try_withdraw(&s1, &w1).is_err());
+    client.cancel_stream(&s1, &employer);
+    assert_eq!(token_client.balance(&w1), 140);
+    assert_eq!(client.get_fiat_terms(&s1).unwrap().reserve, 0);
+
+    // Out of band, and with no last good price once the anchor is reset, the
+    // creation price is used
+    oracle.set_price(&5, &70);
+    assert!(client.try_withdraw(&s2, &w2).is_err());
+    oracle.set_price(&50, &0);
+    client.reset_price_anchor(&eur, &token);
+    env.ledger().with_mut(|li| { li.timestamp = 75; });
+    client.cancel_stream(&s2, &employer);
+    assert_eq!(token_client.balance(&w2), 150);
+    assert_eq!(vault.get_total_liability(&token), 0);
+    assert!(vault.check_invariants(&token));
+}
+
+#[test]
+fn test_batch_withdraw_skips_fiat_stream_without_a_usable_price() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _vault, employer, token) = setup_with_vault(&env);
+    let token_client = soroban_sdk::token::Client::new(&env, &token);
+    let worker = Address::generate(&env);
+    let eur = Symbol::new(&env, "EUR");
+
+    let oracle_id = env.register(mock_oracle::MockOracle, ());
+    let oracle = mock_oracle::MockOracleClient::new(&env, &oracle_id);
+    oracle.set_price(&50, &0);
+    client.set_price_oracle(&eur, &oracle_id, &60u64, &2_000u32, &500u32);
+
+    env.ledger().with_mut(|li| { li.timestamp = 0; });
+    let fiat = client.create_fiat_stream(&employer, &worker, &terms(&token, 1, 100), &eur);
+    let plain = client.create_stream(&employer, &worker, &token, &1, &0u64, &0u64, &100u64);
+
+    // The feed is stale: the fiat stream is reported, the other still pays
+    env.ledger().with_mut(|li| { li.timestamp = 70; });
+    let results = client.batch_withdraw(&soroban_sdk::vec![&env, fiat, plain], &worker);
+    assert!(!results.get(0).unwrap().success);
+    assert_eq!(results.get(1).unwrap().amount, 70);
+    assert_eq!(token_client.balance(&worker), 70);
+    assert_eq!(client.get_stream(&fiat).unwrap().withdrawn_amount, 0);
+
+    // Once the price is fresh again it pays out
+    oracle.set_price(&50, &70);
+    let results = client.batch_withdraw(&soroban_sdk::vec![&env, fiat], &worker);
+    assert_eq!(results.get(0).unwrap().amount, 140);
+}
@@ -54,6 +54,9 @@ pub fn run_fuzz_iteration(
         }
         _ => {}
     }
+
+    // Invariant: tracked treasury never exceeds the real token balance
+    assert!(client.check_invariants(token_id));
 }
 
 #[test]
@@ -1,7 +1,10 @@
 #![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Symbol, token};
+use soroban_sdk::{contract, contractimpl, contracttype, symbol_short, Address, BytesN, Env, Symbol, Vec, token};
 use quipay_common::{QuipayError, require_positive_amount};
 
+mod strategy;
+pub use strategy::{StrategyClient, StrategyConfig, YieldStrategy};
+
 #[cfg(test)]
 mod test;
 
@@ -24,10 +27,25 @@ pub enum StateKey {
     // Persistent storage - survives upgrades
     Admin,
     Version,
-    AuthorizedContract, // Contract authorized to modify liabilities (e.g., PayrollStream)
+    AuthorizedContract, // Legacy single authorized contract, migrated into AuthorizedContracts
     // Additional state that should persist across upgrades
     TreasuryBalance(Address), // Funds held for payroll (Token -> Amount)
     TotalLiability(Address),  // Amount owed to recipients (Token -> Amount)
+    SourceLiability(Address, Address), // Liability reserved by one source ((Source, Token) -> Amount)
+    AuthorizedContracts, // Contracts authorized to modify liabilities (e.g., PayrollStream deployments)
+    SourceOpenTokens(Address), // Number of tokens with non-zero liability for a source
+    Strategy(Address),         // Yield strategy adapter for a token (Token -> StrategyConfig)
+    StrategyDeployed(Address), // Principal currently deployed to the strategy (Token -> Amount)
+    ReservePolicy(Address),    // Reserve requirements for a token (Token -> ReservePolicy)
+    OutflowRate(Address),      // Aggregate per-second streaming outflow (Token -> Amount)
+    SourceOutflowRate(Address, Address), // Outflow reported by one source ((Source, Token) -> Amount)
+    Approvers,                 // M-of-N approver set for large withdrawals
+    SpendingLimit(Address),    // Direct withdrawals allowed per day before approvals are needed (Token -> Amount)
+    PeriodSpent(Address),      // Direct withdrawals in the current day (Token -> (Day, Amount))
+    NextProposalId,
+    WithdrawalProposal(u64),
+    RunwayAlert(Address),      // Runway threshold in days that triggers a low-runway event (Token -> u32)
+    RunwayAlerted(Address),    // Set while runway is below the threshold so the event fires once per crossing
 }
 
 #[contracttype]
@@ -39,9 +57,106 @@ pub struct VersionInfo {
     pub upgraded_at: u64,
 }
 
+/// Snapshot comparing the tracked treasury balance against the token contract's
+/// actual balance for the vault address.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReconciliationReport {
+    pub token: Address,
+    pub tracked: i128,
+    pub actual: i128,
+    /// `actual - tracked`. Positive means stray funds the vault does not account for,
+    /// negative means the tracked balance is not fully backed by tokens.
+    pub discrepancy: i128,
+}
+
+/// Liquidity buffer kept on top of liabilities for a token. Both requirements are
+/// optional (zero disables them); the stricter one applies.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReservePolicy {
+    /// Liquid funds must be at least this share of liability, in basis points (e.g. 11000 = 110%).
+    pub min_ratio_bps: u32,
+    /// Liquid funds must cover liability plus this many days of aggregate streaming outflow.
+    pub runway_days: u32,
+}
+
+/// Dashboard view of a token's reserve position.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReserveStatus {
+    pub liquid_balance: i128,
+    pub deployed: i128,
+    pub liability: i128,
+    /// Aggregate per-second outflow of active streams.
+    pub outflow_rate: i128,
+    /// Liquid funds the policy requires to be held.
+    pub required_reserve: i128,
+    /// Liquid funds above the requirement, available to withdraw or deploy.
+    pub withdrawable: i128,
+    /// `(liquid + deployed) / liability` in basis points; `u32::MAX` when there is no liability.
+    pub coverage_ratio_bps: u32,
+    /// Whole days `(liquid + deployed)` sustains the current outflow; `u64::MAX` when nothing streams.
+    pub runway_days: u64,
+}
+
+/// When a token's treasury runs dry at the current streaming outflow, and what it
+/// takes to fund a planning horizon.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunwayProjection {
+    /// Liquid plus deployed funds.
+    pub holdings: i128,
+    /// Aggregate per-second outflow of active streams. Streams past their end
+    /// count until they close or the stream contract's `expire_streams` runs.
+    pub outflow_rate: i128,
+    /// Ledger timestamp at which holdings reach zero; `u64::MAX` when nothing streams.
+    pub depletion_ts: u64,
+    pub horizon_days: u32,
+    /// Outflow over the horizon.
+    pub horizon_outflow: i128,
+    /// Deposit needed for holdings to cover the horizon; zero when already covered.
+    pub deposit_needed: i128,
+}
+
+/// Contract-level approvers for withdrawals above the spending limit.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ApproverSet {
+    pub approvers: Vec<Address>,
+    pub threshold: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum ProposalStatus {
+    Pending = 0,
+    Executed = 1,
+    Cancelled = 2,
+}
+
+/// A withdrawal above the spending limit awaiting M-of-N approval.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WithdrawalProposal {
+    pub id: u64,
+    pub proposer: Address,
+    pub to: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub approvals: Vec<Address>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub status: ProposalStatus,
+}
+
 #[contract]
 pub struct PayrollVault;
 
+const BPS_DENOMINATOR: u32 = 10_000;
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
 // Event symbols
 const UPGRADED: Symbol = symbol_short!("upgrd");
 #[allow(dead_code)]
@@ -142,13 +257,20 @@ impl PayrollVault {
         require_positive_amount!(amount);
         
         // Update treasury balance
+        // Credit what actually arrived rather than `amount`, so fee-on-transfer
+        // tokens cannot leave the tracked balance above the real one.
+        let token_client = token::Client::new(&e, &token);
+        let this = e.current_contract_address();
+        let before = token_client.balance(&this);
+        token_client.transfer(&from, &this, &amount);
+        let received = token_client.balance(&this) - before;
+
         let key = StateKey::TreasuryBalance(token.clone());
         let current_balance: i128 = e.storage().persistent().get(&key).unwrap_or(0);
-        e.storage().persistent().set(&key, &(current_balance + amount));
-        
-        let token_client = token::Client::new(&e, &token);
-        token_client.transfer(&from, &e.current_contract_address(), &amount);
+        e.storage().persistent().set(&key, &(current_balance + received));
+        Self::check_runway(&e, &token);
 
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("vault"),
@@ -156,14 +278,83 @@ impl PayrollVault {
                 from.clone(),
                 token.clone(),
             ),
-            (amount),
+            received,
         );
 
         Ok(())
     }
 
+    /// Compare the tracked treasury balance with the real token balance held by the vault.
+    /// Emits a `reconcile` event carrying both figures and the discrepancy.
+    pub fn reconcile(e: Env, token: Address) -> ReconciliationReport {
+        let report = Self::reconciliation_report(&e, &token);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("reconcile"),
+                token.clone(),
+            ),
+            (report.tracked, report.actual, report.discrepancy),
+        );
+
+        report
+    }
+
+    /// Recover tokens held by the vault but not reflected in `TreasuryBalance`
+    /// (direct transfers to the vault address, rounding from fee-on-transfer tokens).
+    ///
+    /// If `to` is the vault itself the stray amount is credited to the treasury,
+    /// otherwise it is transferred out to `to`. Returns the swept amount.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn sweep_untracked(e: Env, token: Address, to: Address) -> Result<i128, QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        let report = Self::reconciliation_report(&e, &token);
+        if report.discrepancy <= 0 {
+            return Err(QuipayError::InsufficientBalance);
+        }
+        let amount = report.discrepancy;
+
+        if to == e.current_contract_address() {
+            e.storage()
+                .persistent()
+                .set(&StateKey::TreasuryBalance(token.clone()), &(report.tracked + amount));
+        } else {
+            let token_client = token::Client::new(&e, &token);
+            token_client.transfer(&e.current_contract_address(), &to, &amount);
+        }
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("swept"),
+                to.clone(),
+                token.clone(),
+            ),
+            amount,
+        );
+
+        Ok(amount)
+    }
+
+    /// Accounting invariant used by tests and fuzzing:
+    /// `TotalLiability <= TreasuryBalance <= token balance`, with neither side negative.
+    pub fn check_invariants(e: Env, token: Address) -> bool {
+        let report = Self::reconciliation_report(&e, &token);
+        let liability = Self::get_total_liability(e.clone(), token);
+        liability >= 0 && liability <= report.tracked && report.tracked <= report.actual
+    }
+
     /// Check if the treasury is solvent for a given token after adding `additional_liability`.
-    /// Returns true if balance >= current_liability + additional_liability.
+    /// Returns true if balance + deployed principal >= current_liability + additional_liability.
+    /// Principal deployed to a yield strategy counts because it is recalled on demand.
     pub fn check_solvency(e: Env, token: Address, additional_liability: i128) -> bool {
         if additional_liability < 0 {
             return false;
@@ -174,13 +365,14 @@ impl PayrollVault {
             .persistent()
             .get(&StateKey::TreasuryBalance(token.clone()))
             .unwrap_or(0);
+        let deployed = Self::get_deployed(e.clone(), token.clone());
         let liability: i128 = e
             .storage()
             .persistent()
             .get(&StateKey::TotalLiability(token))
             .unwrap_or(0);
 
-        balance >= liability.saturating_add(additional_liability)
+        balance.saturating_add(deployed) >= liability.saturating_add(additional_liability)
     }
 
     /// Returns the available balance for a token (balance - liability).
@@ -199,38 +391,259 @@ impl PayrollVault {
     }
 
     /// Withdraw free funds from the treasury.
-    /// Enforces `amount <= available_balance(token)`.
+    /// Enforces `amount <= available_balance(token)`. Once a spending limit is set,
+    /// direct withdrawals are capped at the limit per day in total; anything beyond
+    /// it must go through `propose_withdrawal`.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
     pub fn withdraw(e: Env, to: Address, token: Address, amount: i128) -> Result<(), QuipayError> {
-        to.require_auth();
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
         require_positive_amount!(amount);
 
-        let available = Self::get_available_balance(e.clone(), token.clone());
-        if amount > available {
-            return Err(QuipayError::InsufficientBalance);
+        if let Some(limit) = Self::get_spending_limit(e.clone(), token.clone()) {
+            let spent = Self::get_period_spent(e.clone(), token.clone());
+            if spent.saturating_add(amount) > limit {
+                return Err(QuipayError::ApprovalRequired);
+            }
+            let day = e.ledger().timestamp() / SECONDS_PER_DAY;
+            e.storage()
+                .persistent()
+                .set(&StateKey::PeriodSpent(token.clone()), &(day, spent + amount));
         }
 
-        let balance_key = StateKey::TreasuryBalance(token.clone());
-        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
+        Self::withdraw_free(&e, &to, &token, amount)
+    }
 
-        // If the invariant holds, this should never underflow.
-        e.storage().persistent().set(&balance_key, &(balance - amount));
+    /// Direct withdrawals of `token` counted against today's spending limit
+    pub fn get_period_spent(e: Env, token: Address) -> i128 {
+        let day = e.ledger().timestamp() / SECONDS_PER_DAY;
+        match e
+            .storage()
+            .persistent()
+            .get::<StateKey, (u64, i128)>(&StateKey::PeriodSpent(token))
+        {
+            Some((spent_day, spent)) if spent_day == day => spent,
+            _ => 0,
+        }
+    }
 
-        let token_client = token::Client::new(&e, &token);
-        token_client.transfer(&e.current_contract_address(), &to, &amount);
+    /// Configure the approvers whose M-of-N sign-off is needed for withdrawals above
+    /// the spending limit. Approvers may be Stellar accounts or Soroban contract accounts.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn set_approvers(e: Env, approvers: Vec<Address>, threshold: u32) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        if threshold == 0 || threshold > approvers.len() {
+            return Err(QuipayError::InvalidThreshold);
+        }
+        let mut i = 0u32;
+        while i < approvers.len() {
+            let approver = approvers.get(i).unwrap();
+            if approvers.last_index_of(&approver) != Some(i) {
+                return Err(QuipayError::InvalidAddress);
+            }
+            i += 1;
+        }
+
+        let set = ApproverSet { approvers, threshold };
+        e.storage().persistent().set(&StateKey::Approvers, &set);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("vault"), symbol_short!("approvers")),
+            (set.approvers.len(), threshold),
+        );
+
+        Ok(())
+    }
+
+    /// Get the configured approver set (if any)
+    pub fn get_approvers(e: Env) -> Option<ApproverSet> {
+        e.storage().persistent().get(&StateKey::Approvers)
+    }
+
+    /// Set the largest amount of `token` that `withdraw` may move per day without approvals.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn set_spending_limit(e: Env, token: Address, limit: i128) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        if limit < 0 {
+            return Err(QuipayError::InvalidAmount);
+        }
+        if Self::get_approvers(e.clone()).is_none() {
+            return Err(QuipayError::ApproversNotSet);
+        }
+
+        e.storage().persistent().set(&StateKey::SpendingLimit(token.clone()), &limit);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("vault"), symbol_short!("limit"), token.clone()),
+            limit,
+        );
+
+        Ok(())
+    }
+
+    /// Get the spending limit for a token (if any)
+    pub fn get_spending_limit(e: Env, token: Address) -> Option<i128> {
+        e.storage().persistent().get(&StateKey::SpendingLimit(token))
+    }
+
+    /// Propose a withdrawal of free funds. The proposer must be an approver and
+    /// counts as the first approval. Returns the proposal id.
+    pub fn propose_withdrawal(
+        e: Env,
+        proposer: Address,
+        to: Address,
+        token: Address,
+        amount: i128,
+        expires_at: u64,
+    ) -> Result<u64, QuipayError> {
+        proposer.require_auth();
+        require_positive_amount!(amount);
+
+        let set = Self::get_approvers(e.clone()).ok_or(QuipayError::ApproversNotSet)?;
+        if !set.approvers.contains(&proposer) {
+            return Err(QuipayError::NotApprover);
+        }
+        let now = e.ledger().timestamp();
+        if expires_at <= now {
+            return Err(QuipayError::ProposalExpired);
+        }
+
+        let id: u64 = e.storage().persistent().get(&StateKey::NextProposalId).unwrap_or(1);
+        e.storage().persistent().set(&StateKey::NextProposalId, &(id + 1));
+
+        let mut approvals = Vec::new(&e);
+        approvals.push_back(proposer.clone());
+        let proposal = WithdrawalProposal {
+            id,
+            proposer: proposer.clone(),
+            to: to.clone(),
+            token: token.clone(),
+            amount,
+            approvals,
+            created_at: now,
+            expires_at,
+            status: ProposalStatus::Pending,
+        };
+        e.storage().persistent().set(&StateKey::WithdrawalProposal(id), &proposal);
 
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("vault"),
-                symbol_short!("withdrawn"),
-                to.clone(),
-                token.clone(),
+                symbol_short!("proposed"),
+                proposer,
+                token,
             ),
-            (amount),
+            (id, to, amount, expires_at),
+        );
+
+        Ok(id)
+    }
+
+    /// Approve a pending withdrawal proposal. Each approver counts once.
+    pub fn approve_withdrawal(e: Env, approver: Address, proposal_id: u64) -> Result<(), QuipayError> {
+        approver.require_auth();
+
+        let set = Self::get_approvers(e.clone()).ok_or(QuipayError::ApproversNotSet)?;
+        if !set.approvers.contains(&approver) {
+            return Err(QuipayError::NotApprover);
+        }
+
+        let mut proposal = Self::pending_proposal(&e, proposal_id)?;
+        if proposal.approvals.contains(&approver) {
+            return Err(QuipayError::AlreadyApproved);
+        }
+        proposal.approvals.push_back(approver.clone());
+        e.storage()
+            .persistent()
+            .set(&StateKey::WithdrawalProposal(proposal_id), &proposal);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("vault"), symbol_short!("approved"), approver),
+            proposal_id,
+        );
+
+        Ok(())
+    }
+
+    /// Execute a proposal once it has reached the approval threshold. Approvals from
+    /// addresses no longer in the approver set do not count. Subject to the same
+    /// balance and reserve checks as `withdraw`.
+    pub fn execute_withdrawal(e: Env, proposal_id: u64) -> Result<(), QuipayError> {
+        let set = Self::get_approvers(e.clone()).ok_or(QuipayError::ApproversNotSet)?;
+        let mut proposal = Self::pending_proposal(&e, proposal_id)?;
+
+        let mut valid = 0u32;
+        for approver in proposal.approvals.iter() {
+            if set.approvers.contains(&approver) {
+                valid += 1;
+            }
+        }
+        if valid < set.threshold {
+            return Err(QuipayError::InsufficientApprovals);
+        }
+
+        proposal.status = ProposalStatus::Executed;
+        e.storage()
+            .persistent()
+            .set(&StateKey::WithdrawalProposal(proposal_id), &proposal);
+
+        Self::withdraw_free(&e, &proposal.to, &proposal.token, proposal.amount)?;
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("vault"), symbol_short!("executed")),
+            proposal_id,
+        );
+
+        Ok(())
+    }
+
+    /// Cancel a pending proposal. Callable by its proposer or the admin.
+    pub fn cancel_withdrawal(e: Env, caller: Address, proposal_id: u64) -> Result<(), QuipayError> {
+        caller.require_auth();
+
+        let mut proposal = Self::pending_proposal(&e, proposal_id)?;
+        let admin = Self::get_admin(e.clone())?;
+        if caller != proposal.proposer && caller != admin {
+            return Err(QuipayError::Unauthorized);
+        }
+
+        proposal.status = ProposalStatus::Cancelled;
+        e.storage()
+            .persistent()
+            .set(&StateKey::WithdrawalProposal(proposal_id), &proposal);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("vault"), symbol_short!("cancelled"), caller),
+            proposal_id,
         );
 
         Ok(())
     }
 
+    /// Get a withdrawal proposal by id
+    pub fn get_withdrawal_proposal(e: Env, proposal_id: u64) -> Option<WithdrawalProposal> {
+        e.storage().persistent().get(&StateKey::WithdrawalProposal(proposal_id))
+    }
+
     /// Adds liability to the vault (e.g., when a stream is created)
     /// Checks if there are enough funds (solvency check)
     /// 
@@ -246,19 +659,20 @@ impl PayrollVault {
             return Err(QuipayError::InvalidAmount);
         }
 
-        let balance_key = StateKey::TreasuryBalance(token.clone());
-        let liability_key = StateKey::TotalLiability(token.clone());
-        
-        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
-        let liability: i128 = e.storage().persistent().get(&liability_key).unwrap_or(0);
-        
-        if balance < liability + amount {
+        if !Self::check_solvency(e.clone(), token.clone(), amount) {
             // panic!("insufficient funds for allocation");
             return Err(QuipayError::InsufficientBalance);
         }
-        
-        e.storage().persistent().set(&liability_key, &(liability + amount));
+        if !Self::check_reserve(e.clone(), token.clone(), amount) {
+            return Err(QuipayError::ReserveRequirement);
+        }
+
+        Self::ensure_liquidity(&e, &token, amount);
 
+        // Manual allocations are booked against the vault itself as their source
+        Self::increase_liability(&e, &e.current_contract_address(), &token, amount);
+
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("vault"),
@@ -266,7 +680,7 @@ impl PayrollVault {
                 token.clone(),
                 symbol_short!("admin"),
             ),
-            (amount),
+            amount,
         );
 
         Ok(())
@@ -286,16 +700,14 @@ impl PayrollVault {
             return Err(QuipayError::InvalidAmount);
         }
 
-        let liability_key = StateKey::TotalLiability(token.clone());
-        let liability: i128 = e.storage().persistent().get(&liability_key).unwrap_or(0);
-        
-        if amount > liability {
+        // Only manual allocations can be released here; contract-sourced liability
+        // is released by the contract that reserved it.
+        if !Self::decrease_liability(&e, &e.current_contract_address(), &token, amount) {
             // panic!("release amount exceeds liability");
              return Err(QuipayError::InvalidAmount); // Or dedicated error
         }
-        
-        e.storage().persistent().set(&liability_key, &(liability - amount));
 
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("vault"),
@@ -303,7 +715,7 @@ impl PayrollVault {
                 token.clone(),
                 symbol_short!("admin"),
             ),
-            (amount),
+            amount,
         );
 
         Ok(())
@@ -322,43 +734,22 @@ impl PayrollVault {
         require_positive_amount!(amount);
         
         let balance_key = StateKey::TreasuryBalance(token.clone());
-        let liability_key = StateKey::TotalLiability(token.clone());
-        
         let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
-        let liability: i128 = e.storage().persistent().get(&liability_key).unwrap_or(0);
         
         if amount > balance {
             // panic!("insufficient treasury balance");
              return Err(QuipayError::InsufficientBalance);
         }
         
-        // Payout reduces liability AND balance
-        // We assume liability was allocated before.
-        // If not allocated, liability could go negative if we subtract blindly.
-        // But here we check if liability >= amount?
-        // Or maybe payout implies liability reduction.
-        // Let's assume payout reduces liability as debt is paid.
-        if amount > liability {
+        // Payout reduces liability AND balance. The amount must have been
+        // allocated by the admin first; contract-sourced liability is paid out
+        // through `payout_liability`.
+        if !Self::decrease_liability(&e, &e.current_contract_address(), &token, amount) {
              // panic!("payout exceeds liability");
              return Err(QuipayError::InvalidAmount);
         }
         
-        e.storage().persistent().set(&liability_key, &(liability - amount));
-        e.storage().persistent().set(&balance_key, &(balance - amount));
-
-        let token_client = token::Client::new(&e, &token);
-        token_client.transfer(&e.current_contract_address(), &to, &amount);
-
-        e.events().publish(
-            (
-                symbol_short!("vault"),
-                symbol_short!("payout"),
-                to.clone(),
-                token.clone(),
-            ),
-            (amount),
-        );
-
+        Self::transfer_out(&e, &to, &token, amount);
         Ok(())
     }
 
@@ -369,6 +760,10 @@ impl PayrollVault {
 
     /// Set the authorized contract that can modify liabilities
     /// Only the admin can call this function
+    ///
+    /// Replaces every previously authorized contract, as when rotating a PayrollStream
+    /// deployment; use `add_authorized_contract` to authorize an additional one.
+    /// Contracts dropped from the set must have no outstanding liability.
     /// 
     /// # Multisig Support
     /// Requires admin authorization. Supports multisig admin accounts for decentralized
@@ -376,75 +771,608 @@ impl PayrollVault {
     pub fn set_authorized_contract(e: Env, contract: Address) {
         let admin: Address = e.storage().persistent().get(&StateKey::Admin).expect("not initialized");
         admin.require_auth();
-        
-        e.storage().persistent().set(&StateKey::AuthorizedContract, &contract);
+
+        for existing in Self::authorized_contracts(&e).iter() {
+            if existing != contract && Self::open_tokens(&e, &existing) > 0 {
+                panic!("replaced contract has outstanding liability");
+            }
+        }
+        let mut contracts = Vec::new(&e);
+        contracts.push_back(contract.clone());
+        e.storage().persistent().set(&StateKey::AuthorizedContracts, &contracts);
+        e.storage().persistent().remove(&StateKey::AuthorizedContract);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("auth_set"),
+                contract.clone(),
+            ),
+            (),
+        );
     }
 
-    /// Get the authorized contract address (if set)
+    /// Get the first authorized contract address (if any)
     pub fn get_authorized_contract(e: Env) -> Option<Address> {
-        e.storage().persistent().get(&StateKey::AuthorizedContract)
+        Self::authorized_contracts(&e).first()
     }
 
-    /// Add liability for a specific token
-    /// Only the authorized contract (e.g., PayrollStream) can call this
-    pub fn add_liability(e: Env, token: Address, amount: i128) {
-        // Require authorization from the authorized contract
-        let authorized: Address = e.storage().persistent().get(&StateKey::AuthorizedContract)
-            .expect("authorized contract not set");
-        authorized.require_auth();
-        
-        if amount <= 0 {
-            panic!("liability amount must be positive");
-        }
+    /// Authorize an additional contract (e.g., a second PayrollStream deployment)
+    /// to reserve and settle liabilities. Each contract's liability is tracked separately.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts for decentralized
+    /// control over which contracts can modify treasury liabilities.
+    pub fn add_authorized_contract(e: Env, contract: Address) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
 
-        if !Self::check_solvency(e.clone(), token.clone(), amount) {
-            panic!("insufficient funds for liability");
+        if !Self::insert_authorized_contract(&e, &contract) {
+            return Err(QuipayError::AlreadyAuthorized);
         }
-        
-        let key = StateKey::TotalLiability(token.clone());
-        let current: i128 = e.storage().persistent().get(&key).unwrap_or(0);
-        e.storage().persistent().set(&key, &(current + amount));
-        
-        // Also update total liability for this token
-        let total_key = StateKey::TotalLiability(token);
-        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
-        e.storage().persistent().set(&total_key, &(total + amount));
-    }
 
-    /// Remove liability for a specific token
-    /// Only the authorized contract (e.g., PayrollStream) can call this
-    pub fn remove_liability(e: Env, token: Address, amount: i128) {
-        // Require authorization from the authorized contract
-        let authorized: Address = e.storage().persistent().get(&StateKey::AuthorizedContract)
-            .expect("authorized contract not set");
-        authorized.require_auth();
-        
-        if amount <= 0 {
-            panic!("removal amount must be positive");
-        }
-        
-        let key = StateKey::TotalLiability(token.clone());
-        let current: i128 = e.storage().persistent().get(&key).unwrap_or(0);
-        
-        if amount > current {
-            panic!("cannot remove more liability than exists");
-        }
-        
-        e.storage().persistent().set(&key, &(current - amount));
-        
-        // Also update total liability for this token
-        let total_key = StateKey::TotalLiability(token);
-        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
-        e.storage().persistent().set(&total_key, &(total - amount));
-    }
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("auth_add"),
+                contract.clone(),
+            ),
+            (),
+        );
 
-    /// Get the liability for a specific token
-    pub fn get_liability(e: Env, token: Address) -> i128 {
-        e.storage().persistent().get(&StateKey::TotalLiability(token)).unwrap_or(0)
+        Ok(())
     }
 
-    /// Get the tracked treasury balance from state
-    pub fn get_treasury_balance(e: Env, token: Address) -> i128 {
+    /// Revoke a contract's authorization. Blocked while the contract still has
+    /// outstanding liability in any token.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts for decentralized
+    /// control over which contracts can modify treasury liabilities.
+    pub fn remove_authorized_contract(e: Env, contract: Address) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        let contracts = Self::authorized_contracts(&e);
+        let index = contracts
+            .first_index_of(&contract)
+            .ok_or(QuipayError::NotAuthorized)?;
+
+        if Self::open_tokens(&e, &contract) > 0 {
+            return Err(QuipayError::OutstandingLiability);
+        }
+
+        let mut contracts = contracts;
+        contracts.remove(index);
+        e.storage().persistent().set(&StateKey::AuthorizedContracts, &contracts);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("auth_rem"),
+                contract.clone(),
+            ),
+            (),
+        );
+
+        Ok(())
+    }
+
+    /// List all contracts authorized to modify liabilities
+    pub fn get_authorized_contracts(e: Env) -> Vec<Address> {
+        Self::authorized_contracts(&e)
+    }
+
+    /// Check whether a contract is authorized to modify liabilities
+    pub fn is_authorized_contract(e: Env, contract: Address) -> bool {
+        Self::authorized_contracts(&e).contains(&contract)
+    }
+
+    /// Add liability for a specific token on behalf of `caller`
+    /// Only an authorized contract (e.g., PayrollStream) can call this
+    pub fn add_liability(e: Env, caller: Address, token: Address, amount: i128) {
+        // Require authorization from the calling authorized contract
+        let authorized = Self::require_authorized_contract(&e, caller);
+        
+        if amount <= 0 {
+            panic!("liability amount must be positive");
+        }
+
+        if !Self::check_solvency(e.clone(), token.clone(), amount) {
+            panic!("insufficient funds for liability");
+        }
+        if !Self::check_reserve(e.clone(), token.clone(), amount) {
+            panic!("reserve requirement not met");
+        }
+
+        Self::ensure_liquidity(&e, &token, amount);
+        Self::increase_liability(&e, &authorized, &token, amount);
+    }
+
+    /// Remove liability for a specific token on behalf of `caller`
+    /// Only an authorized contract (e.g., PayrollStream) can call this,
+    /// and only up to the liability that contract reserved itself.
+    pub fn remove_liability(e: Env, caller: Address, token: Address, amount: i128) {
+        // Require authorization from the calling authorized contract
+        let authorized = Self::require_authorized_contract(&e, caller);
+        
+        if amount <= 0 {
+            panic!("removal amount must be positive");
+        }
+        
+        if !Self::decrease_liability(&e, &authorized, &token, amount) {
+            panic!("cannot remove more liability than exists");
+        }
+    }
+
+    /// Settle liability by paying `amount` to `to` (e.g., a stream withdrawal).
+    /// Reduces the caller's liability, the total liability and the treasury balance.
+    /// Only an authorized contract (e.g., PayrollStream) can call this.
+    pub fn payout_liability(e: Env, caller: Address, to: Address, token: Address, amount: i128) {
+        let authorized = Self::require_authorized_contract(&e, caller);
+
+        if amount <= 0 {
+            panic!("payout amount must be positive");
+        }
+
+        let balance = Self::get_treasury_balance(e.clone(), token.clone());
+        if amount > balance {
+            panic!("insufficient treasury balance");
+        }
+
+        if !Self::decrease_liability(&e, &authorized, &token, amount) {
+            panic!("cannot remove more liability than exists");
+        }
+
+        Self::transfer_out(&e, &to, &token, amount);
+    }
+
+    /// Report a new stream's per-second rate so reserve policies can size the
+    /// runway buffer. Call before `add_liability` for the same stream.
+    /// Only an authorized contract (e.g., PayrollStream) can call this.
+    pub fn add_outflow_rate(e: Env, caller: Address, token: Address, rate: i128) {
+        let authorized = Self::require_authorized_contract(&e, caller);
+
+        if rate <= 0 {
+            panic!("rate must be positive");
+        }
+
+        let source_key = StateKey::SourceOutflowRate(authorized, token.clone());
+        let source_rate: i128 = e.storage().persistent().get(&source_key).unwrap_or(0);
+        e.storage().persistent().set(&source_key, &(source_rate + rate));
+
+        let total_key = StateKey::OutflowRate(token.clone());
+        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
+        e.storage().persistent().set(&total_key, &(total + rate));
+        Self::check_runway(&e, &token);
+    }
+
+    /// Withdraw a stream's rate once it is canceled or completed.
+    /// Only an authorized contract (e.g., PayrollStream) can call this.
+    /// Removal is capped at what the source recorded, so streams opened before
+    /// outflow tracking can still be closed.
+    pub fn remove_outflow_rate(e: Env, caller: Address, token: Address, rate: i128) {
+        let authorized = Self::require_authorized_contract(&e, caller);
+
+        if rate <= 0 {
+            panic!("rate must be positive");
+        }
+
+        let source_key = StateKey::SourceOutflowRate(authorized, token.clone());
+        let source_rate: i128 = e.storage().persistent().get(&source_key).unwrap_or(0);
+        let removed = rate.min(source_rate);
+        e.storage().persistent().set(&source_key, &(source_rate - removed));
+
+        let total_key = StateKey::OutflowRate(token.clone());
+        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
+        e.storage().persistent().set(&total_key, &(total - removed));
+        Self::check_runway(&e, &token);
+    }
+
+    /// Get the aggregate per-second outflow of active streams for a token
+    pub fn get_outflow_rate(e: Env, token: Address) -> i128 {
+        e.storage().persistent().get(&StateKey::OutflowRate(token)).unwrap_or(0)
+    }
+
+    /// Configure the reserve policy for a token. `min_ratio_bps` must be 0 (disabled)
+    /// or at least 10000 (100%).
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn set_reserve_policy(e: Env, token: Address, min_ratio_bps: u32, runway_days: u32) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        if min_ratio_bps != 0 && min_ratio_bps < BPS_DENOMINATOR {
+            return Err(QuipayError::InvalidAmount);
+        }
+
+        let policy = ReservePolicy { min_ratio_bps, runway_days };
+        e.storage().persistent().set(&StateKey::ReservePolicy(token.clone()), &policy);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("reserve"),
+                token.clone(),
+            ),
+            (min_ratio_bps, runway_days),
+        );
+
+        Ok(())
+    }
+
+    /// Get the reserve policy for a token (if any)
+    pub fn get_reserve_policy(e: Env, token: Address) -> Option<ReservePolicy> {
+        e.storage().persistent().get(&StateKey::ReservePolicy(token))
+    }
+
+    /// Check whether the treasury (liquid plus recallable strategy funds) still
+    /// meets the reserve policy after adding `additional_liability`.
+    pub fn check_reserve(e: Env, token: Address, additional_liability: i128) -> bool {
+        if additional_liability < 0 {
+            return false;
+        }
+        let liability = Self::get_total_liability(e.clone(), token.clone()).saturating_add(additional_liability);
+        let holdings = Self::get_treasury_balance(e.clone(), token.clone())
+            .saturating_add(Self::get_deployed(e.clone(), token.clone()));
+        holdings >= Self::required_reserve(&e, &token, liability)
+    }
+
+    /// Coverage ratio, runway and reserve headroom for dashboards.
+    pub fn get_reserve_status(e: Env, token: Address) -> ReserveStatus {
+        let liquid_balance = Self::get_treasury_balance(e.clone(), token.clone());
+        let deployed = Self::get_deployed(e.clone(), token.clone());
+        let liability = Self::get_total_liability(e.clone(), token.clone());
+        let outflow_rate = Self::get_outflow_rate(e.clone(), token.clone());
+        let required_reserve = Self::required_reserve(&e, &token, liability);
+        let holdings = liquid_balance + deployed;
+
+        let coverage_ratio_bps = if liability <= 0 {
+            u32::MAX
+        } else {
+            let ratio = holdings.max(0) * i128::from(BPS_DENOMINATOR) / liability;
+            u32::try_from(ratio).unwrap_or(u32::MAX)
+        };
+        let runway_days = match Self::runway_secs(holdings, outflow_rate) {
+            Some(secs) => secs / SECONDS_PER_DAY,
+            None => u64::MAX,
+        };
+
+        ReserveStatus {
+            liquid_balance,
+            deployed,
+            liability,
+            outflow_rate,
+            required_reserve,
+            withdrawable: Self::withdrawable(&e, &token),
+            coverage_ratio_bps,
+            runway_days,
+        }
+    }
+
+    /// Projects when the treasury for `token` is depleted at the current streaming
+    /// outflow and the deposit needed to cover the next `horizon_days`. Ended but
+    /// unwithdrawn streams are included until released by `expire_streams`, so
+    /// the projection errs toward depleting early.
+    pub fn get_runway_projection(e: Env, token: Address, horizon_days: u32) -> RunwayProjection {
+        let holdings = Self::get_treasury_balance(e.clone(), token.clone())
+            .saturating_add(Self::get_deployed(e.clone(), token.clone()));
+        let outflow_rate = Self::get_outflow_rate(e.clone(), token);
+
+        let depletion_ts = match Self::runway_secs(holdings, outflow_rate) {
+            Some(secs) => e.ledger().timestamp().saturating_add(secs),
+            None => u64::MAX,
+        };
+        let horizon_outflow =
+            outflow_rate.saturating_mul(i128::from(u64::from(horizon_days) * SECONDS_PER_DAY));
+
+        RunwayProjection {
+            holdings,
+            outflow_rate,
+            depletion_ts,
+            horizon_days,
+            horizon_outflow,
+            deposit_needed: (horizon_outflow - holdings).max(0),
+        }
+    }
+
+    /// Emit a `low_runway` event whenever the runway for `token` drops below
+    /// `threshold_days`. Zero disables the alert.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn set_runway_alert(e: Env, token: Address, threshold_days: u32) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        e.storage().persistent().remove(&StateKey::RunwayAlerted(token.clone()));
+        if threshold_days == 0 {
+            e.storage().persistent().remove(&StateKey::RunwayAlert(token));
+        } else {
+            e.storage()
+                .persistent()
+                .set(&StateKey::RunwayAlert(token.clone()), &threshold_days);
+            Self::check_runway(&e, &token);
+        }
+        Ok(())
+    }
+
+    /// Get the low-runway alert threshold in days for a token (if any)
+    pub fn get_runway_alert(e: Env, token: Address) -> Option<u32> {
+        e.storage().persistent().get(&StateKey::RunwayAlert(token))
+    }
+
+    /// Get the liability reserved by a single source for a token.
+    /// Manual admin allocations are reported under the vault's own address.
+    pub fn get_source_liability(e: Env, source: Address, token: Address) -> i128 {
+        e.storage()
+            .persistent()
+            .get(&StateKey::SourceLiability(source, token))
+            .unwrap_or(0)
+    }
+
+    /// One-time migration for vaults upgraded from the single-contract layout.
+    /// The legacy `TotalLiability` double-counted stream reservations and mixed
+    /// in manual allocations, so it cannot be split on-chain. The admin passes
+    /// the legacy contract's share (the remaining amount of its open streams)
+    /// and the outstanding manual allocations; these are booked against the
+    /// legacy authorized contract and the vault itself, and the unassigned
+    /// legacy total is replaced by their sum. Returns the amount assigned;
+    /// zero once nothing is left unassigned.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn migrate_legacy_liability(
+        e: Env,
+        token: Address,
+        stream_liability: i128,
+        admin_liability: i128,
+    ) -> Result<i128, QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        let contracts = Self::authorized_contracts(&e);
+        let legacy = contracts.first().ok_or(QuipayError::NotAuthorized)?;
+        let this = e.current_contract_address();
+
+        let mut assigned = Self::get_source_liability(e.clone(), this.clone(), token.clone());
+        for contract in contracts.iter() {
+            assigned += Self::get_source_liability(e.clone(), contract, token.clone());
+        }
+        let total = Self::get_total_liability(e.clone(), token.clone());
+        let unassigned = total - assigned;
+        if unassigned <= 0 {
+            return Ok(0);
+        }
+        // Legacy liability can only shrink: stream reservations were counted twice
+        if stream_liability < 0 || admin_liability < 0 || stream_liability + admin_liability > unassigned {
+            return Err(QuipayError::InvalidAmount);
+        }
+
+        e.storage()
+            .persistent()
+            .set(&StateKey::TotalLiability(token.clone()), &assigned);
+        if stream_liability > 0 {
+            Self::increase_liability(&e, &legacy, &token, stream_liability);
+        }
+        if admin_liability > 0 {
+            Self::increase_liability(&e, &this, &token, admin_liability);
+        }
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("liab_mig"),
+                legacy,
+                token,
+            ),
+            (stream_liability, admin_liability),
+        );
+
+        Ok(stream_liability + admin_liability)
+    }
+
+    /// Register a yield strategy adapter for a token.
+    /// `max_deploy_bps` caps how much of the free (non-liability) balance may be deployed.
+    /// Replacing an adapter requires the current one to hold no deployed principal.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn set_strategy(e: Env, token: Address, adapter: Address, max_deploy_bps: u32) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        if max_deploy_bps > BPS_DENOMINATOR {
+            return Err(QuipayError::InvalidAmount);
+        }
+        if Self::get_deployed(e.clone(), token.clone()) > 0 {
+            return Err(QuipayError::StrategyHasFunds);
+        }
+
+        let config = StrategyConfig { adapter: adapter.clone(), max_deploy_bps };
+        e.storage().persistent().set(&StateKey::Strategy(token.clone()), &config);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("strat_set"),
+                token.clone(),
+                adapter.clone(),
+            ),
+            max_deploy_bps,
+        );
+
+        Ok(())
+    }
+
+    /// Recall the whole position, principal and unharvested yield, and unregister
+    /// the strategy for a token.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn remove_strategy(e: Env, token: Address) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        let config = Self::get_strategy(e.clone(), token.clone()).ok_or(QuipayError::StrategyNotSet)?;
+        let deployed = Self::get_deployed(e.clone(), token.clone());
+        // Nothing can reach the adapter once its config is gone, so take the gains too
+        let position = StrategyClient::new(&e, &config.adapter).balance(&e.current_contract_address(), &token);
+        if position > deployed {
+            Self::pull_from_strategy(&e, &config, &token, position - deployed);
+        }
+        if deployed > 0 {
+            Self::recall(&e, &config, &token, deployed);
+        }
+        e.storage().persistent().remove(&StateKey::Strategy(token.clone()));
+        e.storage().persistent().remove(&StateKey::StrategyDeployed(token.clone()));
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("strat_rem"),
+                token.clone(),
+                config.adapter,
+            ),
+            (),
+        );
+
+        Ok(())
+    }
+
+    /// Move idle funds into the token's strategy. The amount must come out of the
+    /// available (non-liability) balance and stay within the configured cap.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn deploy_to_strategy(e: Env, token: Address, amount: i128) -> Result<(), QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+        require_positive_amount!(amount);
+
+        let config = Self::get_strategy(e.clone(), token.clone()).ok_or(QuipayError::StrategyNotSet)?;
+        if amount > Self::get_available_balance(e.clone(), token.clone()) {
+            return Err(QuipayError::InsufficientBalance);
+        }
+        if amount > Self::withdrawable(&e, &token) {
+            return Err(QuipayError::ReserveRequirement);
+        }
+
+        let deployed = Self::get_deployed(e.clone(), token.clone());
+        let free_total = Self::get_available_balance(e.clone(), token.clone()) + deployed;
+        let cap = free_total * i128::from(config.max_deploy_bps) / i128::from(BPS_DENOMINATOR);
+        if deployed + amount > cap {
+            return Err(QuipayError::StrategyCapExceeded);
+        }
+
+        let balance_key = StateKey::TreasuryBalance(token.clone());
+        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
+        e.storage().persistent().set(&balance_key, &(balance - amount));
+        e.storage()
+            .persistent()
+            .set(&StateKey::StrategyDeployed(token.clone()), &(deployed + amount));
+
+        let token_client = token::Client::new(&e, &token);
+        token_client.transfer(&e.current_contract_address(), &config.adapter, &amount);
+        StrategyClient::new(&e, &config.adapter).deposit(&e.current_contract_address(), &token, &amount);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("deployed"),
+                token.clone(),
+                config.adapter,
+            ),
+            amount,
+        );
+
+        Ok(())
+    }
+
+    /// Pull principal back from the token's strategy into the treasury.
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn recall_from_strategy(e: Env, token: Address, amount: i128) -> Result<i128, QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+        require_positive_amount!(amount);
+
+        let config = Self::get_strategy(e.clone(), token.clone()).ok_or(QuipayError::StrategyNotSet)?;
+        if amount > Self::get_deployed(e.clone(), token.clone()) {
+            return Err(QuipayError::InsufficientBalance);
+        }
+
+        Ok(Self::recall(&e, &config, &token, amount))
+    }
+
+    /// Collect returns above the deployed principal and credit them to the treasury.
+    /// Returns the harvested amount (zero if the position has not grown).
+    ///
+    /// # Multisig Support
+    /// Requires admin authorization. Supports multisig admin accounts where the
+    /// signature threshold must be met at the Stellar network level.
+    pub fn harvest(e: Env, token: Address) -> Result<i128, QuipayError> {
+        let admin = Self::get_admin(e.clone())?;
+        admin.require_auth();
+
+        let config = Self::get_strategy(e.clone(), token.clone()).ok_or(QuipayError::StrategyNotSet)?;
+        let strategy = StrategyClient::new(&e, &config.adapter);
+        let position = strategy.balance(&e.current_contract_address(), &token);
+        let gain = position - Self::get_deployed(e.clone(), token.clone());
+        if gain <= 0 {
+            return Ok(0);
+        }
+
+        let received = Self::pull_from_strategy(&e, &config, &token, gain);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("harvested"),
+                token.clone(),
+                config.adapter,
+            ),
+            received,
+        );
+
+        Ok(received)
+    }
+
+    /// Get the yield strategy registered for a token (if any)
+    pub fn get_strategy(e: Env, token: Address) -> Option<StrategyConfig> {
+        e.storage().persistent().get(&StateKey::Strategy(token))
+    }
+
+    /// Get the principal currently deployed to the token's strategy
+    pub fn get_deployed(e: Env, token: Address) -> i128 {
+        e.storage().persistent().get(&StateKey::StrategyDeployed(token)).unwrap_or(0)
+    }
+
+    /// Get the liability for a specific token
+    pub fn get_liability(e: Env, token: Address) -> i128 {
+        e.storage().persistent().get(&StateKey::TotalLiability(token)).unwrap_or(0)
+    }
+
+    /// Get the tracked treasury balance from state
+    pub fn get_treasury_balance(e: Env, token: Address) -> i128 {
         e.storage().persistent().get(&StateKey::TreasuryBalance(token)).unwrap_or(0)
     }
 
@@ -458,3 +1386,294 @@ impl PayrollVault {
         e.current_contract_address()
     }
 }
+
+impl PayrollVault {
+    /// Authorized contracts, folding in the pre-multi-contract `AuthorizedContract` entry.
+    fn authorized_contracts(e: &Env) -> Vec<Address> {
+        if let Some(contracts) = e.storage().persistent().get(&StateKey::AuthorizedContracts) {
+            return contracts;
+        }
+        let mut contracts = Vec::new(e);
+        if let Some(legacy) = e.storage().persistent().get::<StateKey, Address>(&StateKey::AuthorizedContract) {
+            contracts.push_back(legacy);
+        }
+        contracts
+    }
+
+    /// Returns false if `contract` was already authorized.
+    fn insert_authorized_contract(e: &Env, contract: &Address) -> bool {
+        let mut contracts = Self::authorized_contracts(e);
+        if contracts.contains(contract) {
+            return false;
+        }
+        contracts.push_back(contract.clone());
+        e.storage().persistent().set(&StateKey::AuthorizedContracts, &contracts);
+        e.storage().persistent().remove(&StateKey::AuthorizedContract);
+        true
+    }
+
+    fn require_authorized_contract(e: &Env, caller: Address) -> Address {
+        let contracts = Self::authorized_contracts(e);
+        if contracts.is_empty() {
+            panic!("authorized contract not set");
+        }
+        if !contracts.contains(&caller) {
+            panic!("contract not authorized");
+        }
+        caller.require_auth();
+        caller
+    }
+
+    /// Books `amount` against both the source bucket and the token aggregate.
+    fn increase_liability(e: &Env, source: &Address, token: &Address, amount: i128) {
+        let source_key = StateKey::SourceLiability(source.clone(), token.clone());
+        let source_liability: i128 = e.storage().persistent().get(&source_key).unwrap_or(0);
+        e.storage().persistent().set(&source_key, &(source_liability + amount));
+        if source_liability == 0 {
+            Self::adjust_open_tokens(e, source, true);
+        }
+
+        let total_key = StateKey::TotalLiability(token.clone());
+        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
+        e.storage().persistent().set(&total_key, &(total + amount));
+    }
+
+    /// Inverse of `increase_liability`. Returns false, leaving state untouched,
+    /// if the source has not reserved at least `amount`.
+    fn decrease_liability(e: &Env, source: &Address, token: &Address, amount: i128) -> bool {
+        let source_key = StateKey::SourceLiability(source.clone(), token.clone());
+        let source_liability: i128 = e.storage().persistent().get(&source_key).unwrap_or(0);
+        if amount > source_liability {
+            return false;
+        }
+
+        let total_key = StateKey::TotalLiability(token.clone());
+        let total: i128 = e.storage().persistent().get(&total_key).unwrap_or(0);
+
+        e.storage().persistent().set(&source_key, &(source_liability - amount));
+        e.storage().persistent().set(&total_key, &(total - amount));
+        if source_liability == amount {
+            Self::adjust_open_tokens(e, source, false);
+        }
+        true
+    }
+
+    /// Number of tokens in which `source` still has liability.
+    fn open_tokens(e: &Env, source: &Address) -> u32 {
+        e.storage()
+            .persistent()
+            .get(&StateKey::SourceOpenTokens(source.clone()))
+            .unwrap_or(0)
+    }
+
+    fn adjust_open_tokens(e: &Env, source: &Address, opened: bool) {
+        let key = StateKey::SourceOpenTokens(source.clone());
+        let open: u32 = e.storage().persistent().get(&key).unwrap_or(0);
+        let open = if opened { open + 1 } else { open.saturating_sub(1) };
+        if open == 0 {
+            e.storage().persistent().remove(&key);
+        } else {
+            e.storage().persistent().set(&key, &open);
+        }
+    }
+
+    /// Debits the treasury balance and transfers tokens out of the vault.
+    fn transfer_out(e: &Env, to: &Address, token: &Address, amount: i128) {
+        let balance_key = StateKey::TreasuryBalance(token.clone());
+        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
+        e.storage().persistent().set(&balance_key, &(balance - amount));
+
+        let token_client = token::Client::new(e, token);
+        token_client.transfer(&e.current_contract_address(), to, &amount);
+        Self::check_runway(e, token);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("payout"),
+                to.clone(),
+                token.clone(),
+            ),
+            amount,
+        );
+    }
+
+    /// Moves free funds out of the treasury after balance and reserve checks.
+    fn withdraw_free(e: &Env, to: &Address, token: &Address, amount: i128) -> Result<(), QuipayError> {
+        let available = Self::get_available_balance(e.clone(), token.clone());
+        if amount > available {
+            return Err(QuipayError::InsufficientBalance);
+        }
+        if amount > Self::withdrawable(e, token) {
+            return Err(QuipayError::ReserveRequirement);
+        }
+
+        let balance_key = StateKey::TreasuryBalance(token.clone());
+        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
+
+        // If the invariant holds, this should never underflow.
+        e.storage().persistent().set(&balance_key, &(balance - amount));
+
+        let token_client = token::Client::new(e, token);
+        token_client.transfer(&e.current_contract_address(), to, &amount);
+        Self::check_runway(e, token);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("withdrawn"),
+                to.clone(),
+                token.clone(),
+            ),
+            amount,
+        );
+
+        Ok(())
+    }
+
+    /// Loads a proposal that is still pending and unexpired.
+    fn pending_proposal(e: &Env, proposal_id: u64) -> Result<WithdrawalProposal, QuipayError> {
+        let proposal: WithdrawalProposal = e
+            .storage()
+            .persistent()
+            .get(&StateKey::WithdrawalProposal(proposal_id))
+            .ok_or(QuipayError::ProposalNotFound)?;
+        if proposal.status != ProposalStatus::Pending {
+            return Err(QuipayError::ProposalClosed);
+        }
+        if e.ledger().timestamp() >= proposal.expires_at {
+            return Err(QuipayError::ProposalExpired);
+        }
+        Ok(proposal)
+    }
+
+    /// Funds that must stay liquid for `liability` under the token's reserve policy.
+    /// Without a policy this is just the liability itself.
+    fn required_reserve(e: &Env, token: &Address, liability: i128) -> i128 {
+        let policy = match Self::get_reserve_policy(e.clone(), token.clone()) {
+            Some(policy) => policy,
+            None => return liability,
+        };
+        let by_ratio = liability.saturating_mul(i128::from(policy.min_ratio_bps)) / i128::from(BPS_DENOMINATOR);
+        let runway_secs = u64::from(policy.runway_days) * SECONDS_PER_DAY;
+        let by_runway = liability.saturating_add(
+            Self::get_outflow_rate(e.clone(), token.clone()).saturating_mul(i128::from(runway_secs)),
+        );
+        liability.max(by_ratio).max(by_runway)
+    }
+
+    /// Seconds `holdings` sustain `outflow_rate`, or `None` when nothing streams.
+    fn runway_secs(holdings: i128, outflow_rate: i128) -> Option<u64> {
+        if outflow_rate <= 0 {
+            return None;
+        }
+        Some(u64::try_from(holdings.max(0) / outflow_rate).unwrap_or(u64::MAX))
+    }
+
+    /// Publishes `low_runway` when the runway first falls below the configured
+    /// threshold, and re-arms once it recovers.
+    fn check_runway(e: &Env, token: &Address) {
+        let threshold = match Self::get_runway_alert(e.clone(), token.clone()) {
+            Some(threshold) => threshold,
+            None => return,
+        };
+        let holdings = Self::get_treasury_balance(e.clone(), token.clone())
+            .saturating_add(Self::get_deployed(e.clone(), token.clone()));
+        let runway_days = match Self::runway_secs(holdings, Self::get_outflow_rate(e.clone(), token.clone())) {
+            Some(secs) => secs / SECONDS_PER_DAY,
+            None => u64::MAX,
+        };
+
+        let alerted_key = StateKey::RunwayAlerted(token.clone());
+        let alerted = e.storage().persistent().has(&alerted_key);
+        if runway_days < u64::from(threshold) {
+            if !alerted {
+                e.storage().persistent().set(&alerted_key, &true);
+                #[allow(deprecated)]
+                e.events().publish(
+                    (
+                        symbol_short!("vault"),
+                        Symbol::new(e, "low_runway"),
+                        token.clone(),
+                    ),
+                    (runway_days, threshold),
+                );
+            }
+        } else if alerted {
+            e.storage().persistent().remove(&alerted_key);
+        }
+    }
+
+    /// Liquid funds above the reserve requirement.
+    fn withdrawable(e: &Env, token: &Address) -> i128 {
+        let liability = Self::get_total_liability(e.clone(), token.clone());
+        let required = Self::required_reserve(e, token, liability);
+        (Self::get_treasury_balance(e.clone(), token.clone()) - required).max(0)
+    }
+
+    /// Recalls deployed principal so that the liquid treasury balance covers
+    /// the reserve requirement for current liability plus `additional`.
+    /// Callers check solvency first.
+    fn ensure_liquidity(e: &Env, token: &Address, additional: i128) {
+        let balance = Self::get_treasury_balance(e.clone(), token.clone());
+        let liability = Self::get_total_liability(e.clone(), token.clone());
+        let shortfall = Self::required_reserve(e, token, liability + additional) - balance;
+        if shortfall <= 0 {
+            return;
+        }
+        if let Some(config) = Self::get_strategy(e.clone(), token.clone()) {
+            let deployed = Self::get_deployed(e.clone(), token.clone());
+            Self::recall(e, &config, token, shortfall.min(deployed));
+        }
+    }
+
+    /// Recalls `amount` of principal. Returns what actually arrived, which may be
+    /// less than requested if the strategy has taken a loss.
+    fn recall(e: &Env, config: &StrategyConfig, token: &Address, amount: i128) -> i128 {
+        let deployed = Self::get_deployed(e.clone(), token.clone());
+        e.storage()
+            .persistent()
+            .set(&StateKey::StrategyDeployed(token.clone()), &(deployed - amount));
+
+        let received = Self::pull_from_strategy(e, config, token, amount);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("vault"),
+                symbol_short!("recalled"),
+                token.clone(),
+                config.adapter.clone(),
+            ),
+            (amount, received),
+        );
+
+        received
+    }
+
+    /// Withdraws from the adapter and credits whatever reached the vault.
+    fn pull_from_strategy(e: &Env, config: &StrategyConfig, token: &Address, amount: i128) -> i128 {
+        let token_client = token::Client::new(e, token);
+        let before = token_client.balance(&e.current_contract_address());
+        StrategyClient::new(e, &config.adapter).withdraw(&e.current_contract_address(), token, &amount);
+        let received = token_client.balance(&e.current_contract_address()) - before;
+
+        let balance_key = StateKey::TreasuryBalance(token.clone());
+        let balance: i128 = e.storage().persistent().get(&balance_key).unwrap_or(0);
+        e.storage().persistent().set(&balance_key, &(balance + received));
+        received
+    }
+
+    fn reconciliation_report(e: &Env, token: &Address) -> ReconciliationReport {
+        let tracked = Self::get_treasury_balance(e.clone(), token.clone());
+        let actual = Self::get_balance(e.clone(), token.clone());
+        ReconciliationReport {
+            token: token.clone(),
+            tracked,
+            actual,
+            discrepancy: actual - tracked,
+        }
+    }
+}
@@ -68,6 +68,7 @@ proptest! {
             
             assert!(treasury >= liability, "INVARIANT VIOLATION: Treasury Balance ({}) is less than Total System Liability ({})", treasury, liability);
             assert!(treasury >= 0, "Treasury balance fell below zero: {}", treasury);
+            assert!(client.check_invariants(&token_id), "INVARIANT VIOLATION: Tracked treasury exceeds real token balance");
         }
     }
 }
@@ -0,0 +1,28 @@
+use soroban_sdk::{contractclient, contracttype, Address, Env};
+
+/// Yield strategy registered for a token.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StrategyConfig {
+    /// Adapter contract implementing `YieldStrategy`.
+    pub adapter: Address,
+    /// Maximum share of free (non-liability) funds that may be deployed, in basis points.
+    pub max_deploy_bps: u32,
+}
+
+/// Interface a strategy adapter (e.g., a wrapper around a lending pool) must expose.
+///
+/// The vault transfers tokens to the adapter before calling `deposit`, and expects
+/// `withdraw` to transfer the requested amount back to `owner`.
+#[allow(dead_code)]
+#[contractclient(name = "StrategyClient")]
+pub trait YieldStrategy {
+    /// Credit `amount` of `token` already transferred to the adapter to `owner`.
+    fn deposit(env: Env, owner: Address, token: Address, amount: i128);
+
+    /// Return up to `amount` of `token` to `owner`. Returns the amount actually sent.
+    fn withdraw(env: Env, owner: Address, token: Address, amount: i128) -> i128;
+
+    /// Current value of `owner`'s position in `token`, including accrued yield.
+    fn balance(env: Env, owner: Address, token: Address) -> i128;
+}
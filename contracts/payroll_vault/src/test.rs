@@ -1,7 +1,7 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, BytesN, Env, token};
+use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, BytesN, Env, token};
 use quipay_common::QuipayError;
 
 #[test]
@@ -325,24 +325,24 @@ fn test_liability_tracking() {
     client.deposit(&depositor, &another_token, &10_000);
 
     // Add liability for first token
-    client.add_liability(&token, &500);
+    client.add_liability(&authorized_contract, &token, &500);
     assert_eq!(client.get_liability(&token), 500);
     assert_eq!(client.get_total_liability(&token), 500);
 
     // Add more liability for same token
-    client.add_liability(&token, &300);
+    client.add_liability(&authorized_contract, &token, &300);
     assert_eq!(client.get_liability(&token), 800);
     assert_eq!(client.get_total_liability(&token), 800);
 
     // Add liability for another token
-    client.add_liability(&another_token, &200);
+    client.add_liability(&authorized_contract, &another_token, &200);
     assert_eq!(client.get_liability(&another_token), 200);
     assert_eq!(client.get_liability(&token), 800); // Unchanged
     assert_eq!(client.get_total_liability(&token), 800);
     assert_eq!(client.get_total_liability(&another_token), 200);
 
     // Remove liability
-    client.remove_liability(&token, &400);
+    client.remove_liability(&authorized_contract, &token, &400);
     assert_eq!(client.get_liability(&token), 400);
     assert_eq!(client.get_total_liability(&token), 400);
     assert_eq!(client.get_total_liability(&another_token), 200);
@@ -405,7 +405,7 @@ fn test_check_solvency_prevents_unfunded_liability() {
     client.deposit(&depositor, &token_id, &500);
 
     // This would exceed balance (liability 0 + 501 > balance 500) and should panic
-    let res = client.try_add_liability(&token_id, &501);
+    let res = client.try_add_liability(&authorized_contract, &token_id, &501);
     assert!(res.is_err());
 }
 
@@ -419,13 +419,14 @@ fn test_add_liability_without_authorized_contract_panics() {
     let client = PayrollVaultClient::new(&env, &contract_id);
 
     let admin = Address::generate(&env);
+    let authorized_contract = Address::generate(&env);
     let token = Address::generate(&env);
 
     // Initialize but don't set authorized contract
     client.initialize(&admin);
 
     // Should panic - no authorized contract set
-    client.add_liability(&token, &500);
+    client.add_liability(&authorized_contract, &token, &500);
 }
 
 #[test]
@@ -455,11 +456,11 @@ fn test_remove_more_liability_than_exists_panics() {
     client.deposit(&depositor, &token, &1_000);
 
     // Add some liability
-    client.add_liability(&token, &500);
+    client.add_liability(&authorized_contract, &token, &500);
     assert_eq!(client.get_liability(&token), 500);
 
     // Should panic - trying to remove more than exists
-    client.remove_liability(&token, &600);
+    client.remove_liability(&authorized_contract, &token, &600);
 }
 
 #[test]
@@ -480,7 +481,7 @@ fn test_add_zero_liability_panics() {
     client.set_authorized_contract(&authorized_contract);
 
     // Should panic - zero amount
-    client.add_liability(&token, &0);
+    client.add_liability(&authorized_contract, &token, &0);
 }
 
 #[test]
@@ -510,10 +511,10 @@ fn test_remove_zero_liability_panics() {
     client.deposit(&depositor, &token, &1_000);
 
     // Add some liability first
-    client.add_liability(&token, &500);
+    client.add_liability(&authorized_contract, &token, &500);
 
     // Should panic - zero amount
-    client.remove_liability(&token, &0);
+    client.remove_liability(&authorized_contract, &token, &0);
 }
 
 #[test]
@@ -736,3 +737,948 @@ fn test_multisig_admin_can_perform_all_operations() {
     client.transfer_admin(&new_multisig_admin);
     assert_eq!(client.get_admin(), new_multisig_admin);
 }
+
+// ============================================================================
+// Reconciliation Tests
+// ============================================================================
+
+mod fee_token {
+    use soroban_sdk::{contract, contractimpl, contracttype, Address, Env};
+
+    #[contracttype]
+    pub enum DataKey {
+        Balance(Address),
+    }
+
+    /// Minimal token that burns 1% of every transfer, standing in for fee-on-transfer assets.
+    #[contract]
+    pub struct FeeToken;
+
+    #[contractimpl]
+    impl FeeToken {
+        pub fn mint(e: Env, to: Address, amount: i128) {
+            let balance = Self::balance(e.clone(), to.clone());
+            e.storage().persistent().set(&DataKey::Balance(to), &(balance + amount));
+        }
+
+        pub fn balance(e: Env, id: Address) -> i128 {
+            e.storage().persistent().get(&DataKey::Balance(id)).unwrap_or(0)
+        }
+
+        pub fn transfer(e: Env, from: Address, to: Address, amount: i128) {
+            from.require_auth();
+            let fee = amount / 100;
+            let from_balance = Self::balance(e.clone(), from.clone());
+            let to_balance = Self::balance(e.clone(), to.clone());
+            e.storage().persistent().set(&DataKey::Balance(from), &(from_balance - amount));
+            e.storage().persistent().set(&DataKey::Balance(to), &(to_balance + amount - fee));
+        }
+    }
+}
+
+#[test]
+fn test_reconcile_detects_direct_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_client = token::Client::new(&env, &token_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+
+    token_admin_client.mint(&user, &1000);
+    client.deposit(&user, &token_id, &600);
+
+    let report = client.reconcile(&token_id);
+    assert_eq!(report.tracked, 600);
+    assert_eq!(report.actual, 600);
+    assert_eq!(report.discrepancy, 0);
+
+    // Tokens sent straight to the vault bypass deposit accounting
+    token_client.transfer(&user, &contract_id, &150);
+
+    let report = client.reconcile(&token_id);
+    assert_eq!(report.tracked, 600);
+    assert_eq!(report.actual, 750);
+    assert_eq!(report.discrepancy, 150);
+    assert!(client.check_invariants(&token_id));
+}
+
+#[test]
+fn test_sweep_untracked_to_recipient() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_client = token::Client::new(&env, &token_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    token_admin_client.mint(&user, &1000);
+    client.deposit(&user, &token_id, &500);
+    token_client.transfer(&user, &contract_id, &200);
+
+    assert_eq!(client.sweep_untracked(&token_id, &recipient), 200);
+    assert_eq!(token_client.balance(&recipient), 200);
+    assert_eq!(client.get_treasury_balance(&token_id), 500);
+    assert_eq!(client.reconcile(&token_id).discrepancy, 0);
+
+    // Nothing left to sweep
+    let res = client.try_sweep_untracked(&token_id, &recipient);
+    assert_eq!(res, Err(Ok(QuipayError::InsufficientBalance)));
+}
+
+#[test]
+fn test_sweep_untracked_credits_treasury() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_client = token::Client::new(&env, &token_id);
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+
+    token_admin_client.mint(&user, &1000);
+    token_client.transfer(&user, &contract_id, &300);
+    assert_eq!(client.get_treasury_balance(&token_id), 0);
+
+    // Sweeping to the vault itself credits the stray funds
+    assert_eq!(client.sweep_untracked(&token_id, &contract_id), 300);
+    assert_eq!(client.get_treasury_balance(&token_id), 300);
+    assert_eq!(token_client.balance(&contract_id), 300);
+
+    client.allocate_funds(&token_id, &300);
+    assert!(client.check_invariants(&token_id));
+}
+
+#[test]
+fn test_deposit_fee_on_transfer_token_credits_received_amount() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_id = env.register(fee_token::FeeToken, ());
+    let fee_client = fee_token::FeeTokenClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+    fee_client.mint(&user, &10_000);
+
+    client.deposit(&user, &token_id, &1_000);
+
+    // 1% is burned in transit, so only 990 is tracked
+    assert_eq!(client.get_treasury_balance(&token_id), 990);
+    assert_eq!(client.get_balance(&token_id), 990);
+    assert_eq!(client.reconcile(&token_id).discrepancy, 0);
+    assert!(client.check_invariants(&token_id));
+
+    // Allocating the nominal deposit must fail since it is not fully backed
+    let res = client.try_allocate_funds(&token_id, &1_000);
+    assert_eq!(res, Err(Ok(QuipayError::InsufficientBalance)));
+}
+
+#[test]
+fn test_sweep_untracked_requires_initialization() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let token = Address::generate(&env);
+    let to = Address::generate(&env);
+
+    let res = client.try_sweep_untracked(&token, &to);
+    assert_eq!(res, Err(Ok(QuipayError::NotInitialized)));
+}
+
+#[test]
+fn test_source_liability_split_from_manual_allocations() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let authorized_contract = Address::generate(&env);
+    client.initialize(&admin);
+    client.set_authorized_contract(&authorized_contract);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+    let worker = Address::generate(&env);
+    token_admin_client.mint(&user, &1000);
+    client.deposit(&user, &token_id, &1000);
+
+    client.add_liability(&authorized_contract, &token_id, &400);
+    client.allocate_funds(&token_id, &100);
+    assert_eq!(client.get_source_liability(&authorized_contract, &token_id), 400);
+    assert_eq!(client.get_source_liability(&contract_id, &token_id), 100);
+    assert_eq!(client.get_total_liability(&token_id), 500);
+
+    // Admin cannot release liability reserved by the authorized contract
+    let res = client.try_release_funds(&token_id, &200);
+    assert_eq!(res, Err(Ok(QuipayError::InvalidAmount)));
+
+    // Contract settles part of its liability directly to a worker
+    client.payout_liability(&authorized_contract, &worker, &token_id, &150);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&worker), 150);
+    assert_eq!(client.get_source_liability(&authorized_contract, &token_id), 250);
+    assert_eq!(client.get_total_liability(&token_id), 350);
+    assert_eq!(client.get_treasury_balance(&token_id), 850);
+    assert!(client.check_invariants(&token_id));
+}
+
+// ============================================================================
+// Multiple Authorized Contracts
+// ============================================================================
+
+#[test]
+fn test_multiple_authorized_contracts_track_liability_separately() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let contractors = Address::generate(&env);
+    let employees = Address::generate(&env);
+    client.add_authorized_contract(&contractors);
+    client.add_authorized_contract(&employees);
+
+    let listed = client.get_authorized_contracts();
+    assert_eq!(listed.len(), 2);
+    assert!(client.is_authorized_contract(&contractors));
+    assert!(client.is_authorized_contract(&employees));
+    assert_eq!(
+        client.try_add_authorized_contract(&employees),
+        Err(Ok(QuipayError::AlreadyAuthorized))
+    );
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+    token_admin_client.mint(&user, &1000);
+    client.deposit(&user, &token_id, &1000);
+
+    client.add_liability(&contractors, &token_id, &300);
+    client.add_liability(&employees, &token_id, &500);
+    assert_eq!(client.get_source_liability(&contractors, &token_id), 300);
+    assert_eq!(client.get_source_liability(&employees, &token_id), 500);
+    assert_eq!(client.get_total_liability(&token_id), 800);
+
+    // Solvency is shared across contracts
+    assert!(client.try_add_liability(&contractors, &token_id, &201).is_err());
+
+    // Removal is blocked while the contract still owes funds
+    assert_eq!(
+        client.try_remove_authorized_contract(&contractors),
+        Err(Ok(QuipayError::OutstandingLiability))
+    );
+
+    client.remove_liability(&contractors, &token_id, &300);
+    client.remove_authorized_contract(&contractors);
+    assert!(!client.is_authorized_contract(&contractors));
+    assert_eq!(client.get_authorized_contracts().len(), 1);
+    assert_eq!(client.get_total_liability(&token_id), 500);
+
+    assert_eq!(
+        client.try_remove_authorized_contract(&contractors),
+        Err(Ok(QuipayError::NotAuthorized))
+    );
+}
+
+#[test]
+#[should_panic(expected = "cannot remove more liability than exists")]
+fn test_contract_cannot_release_another_contracts_liability() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let stream_a = Address::generate(&env);
+    let stream_b = Address::generate(&env);
+    client.add_authorized_contract(&stream_a);
+    client.add_authorized_contract(&stream_b);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(&env, &token_id);
+    let user = Address::generate(&env);
+    token_admin_client.mint(&user, &1000);
+    client.deposit(&user, &token_id, &1000);
+
+    client.add_liability(&stream_a, &token_id, &400);
+    client.remove_liability(&stream_b, &token_id, &100);
+}
+
+#[test]
+#[should_panic(expected = "contract not authorized")]
+fn test_unlisted_contract_cannot_add_liability() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+    client.add_authorized_contract(&Address::generate(&env));
+
+    let intruder = Address::generate(&env);
+    let token = Address::generate(&env);
+    client.add_liability(&intruder, &token, &1);
+}
+
+#[test]
+fn test_legacy_authorized_contract_is_migrated() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    // Simulate state written by a pre-upgrade vault
+    let legacy = Address::generate(&env);
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&StateKey::AuthorizedContract, &legacy);
+    });
+    assert!(client.is_authorized_contract(&legacy));
+
+    let second = Address::generate(&env);
+    client.add_authorized_contract(&second);
+    let listed = client.get_authorized_contracts();
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed.get(0).unwrap(), legacy);
+    assert_eq!(listed.get(1).unwrap(), second);
+}
+
+#[test]
+fn test_set_authorized_contract_replaces_the_set() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let user = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token_id).mint(&user, &1000);
+    client.deposit(&user, &token_id, &1000);
+
+    let old_stream = Address::generate(&env);
+    let extra = Address::generate(&env);
+    client.set_authorized_contract(&old_stream);
+    client.add_authorized_contract(&extra);
+
+    // Rotation is refused while a replaced contract still owes funds
+    client.add_liability(&old_stream, &token_id, &100);
+    let new_stream = Address::generate(&env);
+    assert!(client.try_set_authorized_contract(&new_stream).is_err());
+
+    client.remove_liability(&old_stream, &token_id, &100);
+    client.set_authorized_contract(&new_stream);
+    let listed = client.get_authorized_contracts();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed.get(0).unwrap(), new_stream);
+    assert!(!client.is_authorized_contract(&old_stream));
+}
+
+#[test]
+fn test_legacy_liability_is_split_by_source() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let user = Address::generate(&env);
+    token::StellarAssetClient::new(&env, &token_id).mint(&user, &2000);
+    client.deposit(&user, &token_id, &2000);
+
+    // Simulate a pre-upgrade vault: two open streams with 300 and 200 remaining,
+    // each reserved twice by the old add_liability, plus a 150 manual allocation
+    let stream_remaining = [300i128, 200];
+    let stream_share: i128 = stream_remaining.iter().sum();
+    let legacy = Address::generate(&env);
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&StateKey::AuthorizedContract, &legacy);
+        env.storage()
+            .persistent()
+            .set(&StateKey::TotalLiability(token_id.clone()), &(2 * stream_share + 150));
+    });
+    assert!(client.try_remove_liability(&legacy, &token_id, &100).is_err());
+
+    // Liability reserved after the upgrade is already tracked and left alone
+    let second = Address::generate(&env);
+    client.add_authorized_contract(&second);
+    client.add_liability(&second, &token_id, &100);
+
+    assert_eq!(
+        client.try_migrate_legacy_liability(&token_id, &(2 * stream_share), &151),
+        Err(Ok(QuipayError::InvalidAmount))
+    );
+    assert_eq!(client.migrate_legacy_liability(&token_id, &stream_share, &150), 650);
+    assert_eq!(client.get_source_liability(&legacy, &token_id), stream_share);
+    assert_eq!(client.get_source_liability(&contract_id, &token_id), 150);
+    assert_eq!(client.get_source_liability(&second, &token_id), 100);
+    assert_eq!(client.get_total_liability(&token_id), stream_share + 150 + 100);
+    assert_eq!(client.migrate_legacy_liability(&token_id, &stream_share, &150), 0);
+
+    // Each source can now settle exactly what it owes
+    let recipient = Address::generate(&env);
+    client.payout_liability(&legacy, &recipient, &token_id, &stream_remaining[0]);
+    client.remove_liability(&legacy, &token_id, &stream_remaining[1]);
+    client.release_funds(&token_id, &150);
+    assert_eq!(client.get_total_liability(&token_id), 100);
+    assert_eq!(
+        client.try_remove_authorized_contract(&second),
+        Err(Ok(QuipayError::OutstandingLiability))
+    );
+    client.remove_authorized_contract(&legacy);
+}
+
+// ============================================================================
+// Yield Strategies
+// ============================================================================
+
+mod mock_strategy {
+    use soroban_sdk::{contract, contractimpl, contracttype, token, Address, Env};
+
+    #[contracttype]
+    pub enum DataKey {
+        Principal(Address, Address),
+        Yield(Address, Address),
+    }
+
+    /// Stands in for a lending pool: holds deposits and reports configurable yield.
+    #[contract]
+    pub struct MockStrategy;
+
+    #[contractimpl]
+    impl MockStrategy {
+        pub fn deposit(e: Env, owner: Address, token: Address, amount: i128) {
+            owner.require_auth();
+            let key = DataKey::Principal(owner, token);
+            let current: i128 = e.storage().persistent().get(&key).unwrap_or(0);
+            e.storage().persistent().set(&key, &(current + amount));
+        }
+
+        pub fn withdraw(e: Env, owner: Address, token: Address, amount: i128) -> i128 {
+            owner.require_auth();
+            let total = Self::balance(e.clone(), owner.clone(), token.clone());
+            let sent = amount.min(total);
+
+            // Pay out of accrued yield first, then principal
+            let yield_key = DataKey::Yield(owner.clone(), token.clone());
+            let accrued: i128 = e.storage().persistent().get(&yield_key).unwrap_or(0);
+            let from_yield = sent.min(accrued);
+            e.storage().persistent().set(&yield_key, &(accrued - from_yield));
+            let principal_key = DataKey::Principal(owner.clone(), token.clone());
+            let principal: i128 = e.storage().persistent().get(&principal_key).unwrap_or(0);
+            e.storage().persistent().set(&principal_key, &(principal - (sent - from_yield)));
+
+            token::Client::new(&e, &token).transfer(&e.current_contract_address(), &owner, &sent);
+            sent
+        }
+
+        pub fn balance(e: Env, owner: Address, token: Address) -> i128 {
+            let principal: i128 = e
+                .storage()
+                .persistent()
+                .get(&DataKey::Principal(owner.clone(), token.clone()))
+                .unwrap_or(0);
+            let accrued: i128 = e
+                .storage()
+                .persistent()
+                .get(&DataKey::Yield(owner, token))
+                .unwrap_or(0);
+            principal + accrued
+        }
+
+        /// Test hook: record `amount` of yield (the caller mints the backing tokens).
+        pub fn accrue(e: Env, owner: Address, token: Address, amount: i128) {
+            let key = DataKey::Yield(owner, token);
+            let current: i128 = e.storage().persistent().get(&key).unwrap_or(0);
+            e.storage().persistent().set(&key, &(current + amount));
+        }
+    }
+}
+
+fn setup_strategy(
+    env: &Env,
+) -> (
+    PayrollVaultClient<'_>,
+    Address,
+    Address,
+    token::StellarAssetClient<'_>,
+    mock_strategy::MockStrategyClient<'_>,
+) {
+    let contract_id = env.register(PayrollVault, ());
+    let client = PayrollVaultClient::new(env, &contract_id);
+    let admin = Address::generate(env);
+    client.initialize(&admin);
+
+    let token_admin = Address::generate(env);
+    let token_id = env.register_stellar_asset_contract_v2(token_admin).address();
+    let token_admin_client = token::StellarAssetClient::new(env, &token_id);
+    let user = Address::generate(env);
+    token_admin_client.mint(&user, &10_000);
+    client.deposit(&user, &token_id, &10_000);
+
+    let strategy_id = env.register(mock_strategy::MockStrategy, ());
+    let strategy = mock_strategy::MockStrategyClient::new(env, &strategy_id);
+
+    (client, contract_id, token_id, token_admin_client, strategy)
+}
+
+#[test]
+fn test_strategy_deploy_respects_cap_and_liability() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, contract_id, token_id, _, strategy) = setup_strategy(&env);
+
+    assert_eq!(
+        client.try_deploy_to_strategy(&token_id, &100),
+        Err(Ok(QuipayError::StrategyNotSet))
+    );
+    assert_eq!(
+        client.try_set_strategy(&token_id, &strategy.address, &10_001),
+        Err(Ok(QuipayError::InvalidAmount))
+    );
+
+    // At most half of the free balance may be deployed
+    client.set_strategy(&token_id, &strategy.address, &5_000);
+    client.allocate_funds(&token_id, &4_000);
+
+    // Free = 10_000 - 4_000 = 6_000, cap = 3_000
+    assert_eq!(
+        client.try_deploy_to_strategy(&token_id, &3_001),
+        Err(Ok(QuipayError::StrategyCapExceeded))
+    );
+    client.deploy_to_strategy(&token_id, &2_000);
+    client.deploy_to_strategy(&token_id, &1_000);
+    assert_eq!(client.get_deployed(&token_id), 3_000);
+    assert_eq!(client.get_treasury_balance(&token_id), 7_000);
+    assert_eq!(strategy.balance(&contract_id, &token_id), 3_000);
+    assert_eq!(client.get_available_balance(&token_id), 3_000);
+    assert!(client.check_invariants(&token_id));
+
+    // Cannot replace an adapter that still holds funds
+    let other = Address::generate(&env);
+    assert_eq!(
+        client.try_set_strategy(&token_id, &other, &5_000),
+        Err(Ok(QuipayError::StrategyHasFunds))
+    );
+}
+
+#[test]
+fn test_strategy_harvest_credits_treasury() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, contract_id, token_id, token_admin_client, strategy) = setup_strategy(&env);
+
+    client.set_strategy(&token_id, &strategy.address, &10_000);
+    client.deploy_to_strategy(&token_id, &5_000);
+    assert_eq!(client.harvest(&token_id), 0);
+
+    // Simulate 250 of interest earned by the pool
+    token_admin_client.mint(&strategy.address, &250);
+    strategy.accrue(&contract_id, &token_id, &250);
+
+    assert_eq!(client.harvest(&token_id), 250);
+    assert_eq!(client.get_treasury_balance(&token_id), 5_250);
+    assert_eq!(client.get_deployed(&token_id), 5_000);
+    assert_eq!(strategy.balance(&contract_id, &token_id), 5_000);
+
+    assert_eq!(client.recall_from_strategy(&token_id, &2_000), 2_000);
+    assert_eq!(client.get_deployed(&token_id), 3_000);
+    assert_eq!(client.get_treasury_balance(&token_id), 7_250);
+
+    client.remove_strategy(&token_id);
+    assert_eq!(client.get_strategy(&token_id), None);
+    assert_eq!(client.get_deployed(&token_id), 0);
+    assert_eq!(client.get_treasury_balance(&token_id), 10_250);
+    assert!(client.check_invariants(&token_id));
+}
+
+#[test]
+fn test_remove_strategy_recalls_unharvested_yield() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, contract_id, token_id, token_admin_client, strategy) = setup_strategy(&env);
+
+    client.set_strategy(&token_id, &strategy.address, &10_000);
+    client.deploy_to_strategy(&token_id, &4_000);
+    token_admin_client.mint(&strategy.address, &150);
+    strategy.accrue(&contract_id, &token_id, &150);
+
+    client.remove_strategy(&token_id);
+    assert_eq!(strategy.balance(&contract_id, &token_id), 0);
+    assert_eq!(client.get_treasury_balance(&token_id), 10_150);
+    assert!(client.check_invariants(&token_id));
+}
+
+#[test]
+fn test_strategy_recalled_automatically_for_new_liability() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, contract_id, token_id, _, strategy) = setup_strategy(&env);
+    let stream_contract = Address::generate(&env);
+    client.add_authorized_contract(&stream_contract);
+
+    client.set_strategy(&token_id, &strategy.address, &8_000);
+    client.deploy_to_strategy(&token_id, &8_000);
+    assert_eq!(client.get_treasury_balance(&token_id), 2_000);
+
+    // Deployed principal still counts toward solvency...
+    assert!(client.check_solvency(&token_id, &9_000));
+    assert!(!client.check_solvency(&token_id, &10_001));
+
+    // ...and is pulled back when a new liability needs it
+    client.add_liability(&stream_contract, &token_id, &6_500);
+    assert_eq!(client.get_treasury_balance(&token_id), 6_500);
+    assert_eq!(client.get_deployed(&token_id), 3_500);
+    assert_eq!(strategy.balance(&contract_id, &token_id), 3_500);
+    assert!(client.check_invariants(&token_id));
+
+    // Liquid funds always cover liabilities, so payouts never touch the strategy
+    let worker = Address::generate(&env);
+    client.payout_liability(&stream_contract, &worker, &token_id, &6_500);
+    assert_eq!(client.get_total_liability(&token_id), 0);
+}
+
+// ============================================================================
+// Reserve Policy
+// ============================================================================
+
+#[test]
+fn test_reserve_ratio_limits_withdraw_and_deploy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, strategy) = setup_strategy(&env);
+    let employer = Address::generate(&env);
+
+    assert_eq!(
+        client.try_set_reserve_policy(&token_id, &9_000u32, &0u32),
+        Err(Ok(QuipayError::InvalidAmount))
+    );
+    client.set_reserve_policy(&token_id, &11_000u32, &0u32);
+    client.allocate_funds(&token_id, &5_000);
+
+    // 110% of 5_000 must stay liquid, leaving 4_500 withdrawable
+    let status = client.get_reserve_status(&token_id);
+    assert_eq!(status.required_reserve, 5_500);
+    assert_eq!(status.withdrawable, 4_500);
+    assert_eq!(status.coverage_ratio_bps, 20_000);
+    assert_eq!(status.runway_days, u64::MAX);
+
+    // Solvent, but 9_100 of liability would need 10_010 in reserve
+    assert_eq!(
+        client.try_allocate_funds(&token_id, &4_100),
+        Err(Ok(QuipayError::ReserveRequirement))
+    );
+
+    assert_eq!(
+        client.try_withdraw(&employer, &token_id, &4_501),
+        Err(Ok(QuipayError::ReserveRequirement))
+    );
+
+    client.set_strategy(&token_id, &strategy.address, &10_000);
+    assert_eq!(
+        client.try_deploy_to_strategy(&token_id, &4_501),
+        Err(Ok(QuipayError::ReserveRequirement))
+    );
+    client.deploy_to_strategy(&token_id, &4_000);
+    client.withdraw(&employer, &token_id, &500);
+    assert_eq!(client.get_reserve_status(&token_id).withdrawable, 0);
+}
+
+#[test]
+fn test_reserve_runway_uses_streaming_outflow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let stream_contract = Address::generate(&env);
+    client.add_authorized_contract(&stream_contract);
+
+    // Keep two days of outflow liquid on top of liabilities
+    client.set_reserve_policy(&token_id, &0u32, &2u32);
+
+    // 1 per second is 172_800 over two days, far beyond the 10_000 treasury
+    client.add_outflow_rate(&stream_contract, &token_id, &1);
+    assert!(!client.check_reserve(&token_id, &0));
+    assert!(client.try_add_liability(&stream_contract, &token_id, &100).is_err());
+
+    client.remove_outflow_rate(&stream_contract, &token_id, &1);
+    assert!(client.check_reserve(&token_id, &10_000));
+    client.add_liability(&stream_contract, &token_id, &4_000);
+
+    let status = client.get_reserve_status(&token_id);
+    assert_eq!(status.outflow_rate, 0);
+    assert_eq!(status.required_reserve, 4_000);
+    assert_eq!(status.coverage_ratio_bps, 25_000);
+}
+
+#[test]
+fn test_outflow_rate_is_tracked_per_source() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let stream_a = Address::generate(&env);
+    let stream_b = Address::generate(&env);
+    client.add_authorized_contract(&stream_a);
+    client.add_authorized_contract(&stream_b);
+
+    client.add_outflow_rate(&stream_a, &token_id, &5);
+    // Another source cannot release A's outflow
+    client.remove_outflow_rate(&stream_b, &token_id, &5);
+    assert_eq!(client.get_outflow_rate(&token_id), 5);
+
+    // Streams that predate outflow tracking release more than was recorded
+    client.add_outflow_rate(&stream_b, &token_id, &2);
+    client.remove_outflow_rate(&stream_b, &token_id, &3);
+    assert_eq!(client.get_outflow_rate(&token_id), 5);
+    client.remove_outflow_rate(&stream_a, &token_id, &5);
+    assert_eq!(client.get_outflow_rate(&token_id), 0);
+}
+
+#[test]
+fn test_runway_projection_and_low_runway_alert() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, contract_id, token_id, _, _) = setup_strategy(&env);
+    let stream_contract = Address::generate(&env);
+    client.add_authorized_contract(&stream_contract);
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let idle = client.get_runway_projection(&token_id, &30u32);
+    assert_eq!(idle.depletion_ts, u64::MAX);
+    assert_eq!(idle.deposit_needed, 0);
+
+    // 10_000 of holdings at 1 per second lasts 10_000 seconds
+    client.add_outflow_rate(&stream_contract, &token_id, &1);
+    let projection = client.get_runway_projection(&token_id, &1u32);
+    assert_eq!(projection.holdings, 10_000);
+    assert_eq!(projection.depletion_ts, 1_000 + 10_000);
+    assert_eq!(projection.horizon_outflow, 86_400);
+    assert_eq!(projection.deposit_needed, 86_400 - 10_000);
+
+    let alerted = |env: &Env| {
+        env.as_contract(&contract_id, || {
+            env.storage()
+                .persistent()
+                .has(&StateKey::RunwayAlerted(token_id.clone()))
+        })
+    };
+
+    // Under a day of runway trips a one-day threshold immediately
+    client.set_runway_alert(&token_id, &1u32);
+    assert_eq!(client.get_runway_alert(&token_id), Some(1));
+    assert!(alerted(&env));
+
+    // Dropping the outflow restores the runway and re-arms the alert
+    client.remove_outflow_rate(&stream_contract, &token_id, &1);
+    assert!(!alerted(&env));
+
+    client.set_runway_alert(&token_id, &0u32);
+    assert_eq!(client.get_runway_alert(&token_id), None);
+    client.add_outflow_rate(&stream_contract, &token_id, &1);
+    assert!(!alerted(&env));
+}
+
+// ============================================================================
+// Withdrawal Proposals
+// ============================================================================
+
+#[test]
+fn test_withdrawal_above_limit_requires_m_of_n_approvals() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let a1 = Address::generate(&env);
+    let a2 = Address::generate(&env);
+    let a3 = Address::generate(&env);
+    let outsider = Address::generate(&env);
+    let recipient = Address::generate(&env);
+
+    assert_eq!(
+        client.try_set_spending_limit(&token_id, &1_000),
+        Err(Ok(QuipayError::ApproversNotSet))
+    );
+    assert_eq!(
+        client.try_set_approvers(&soroban_sdk::vec![&env, a1.clone(), a2.clone()], &3),
+        Err(Ok(QuipayError::InvalidThreshold))
+    );
+    assert_eq!(
+        client.try_set_approvers(&soroban_sdk::vec![&env, a1.clone(), a1.clone()], &1),
+        Err(Ok(QuipayError::InvalidAddress))
+    );
+    client.set_approvers(&soroban_sdk::vec![&env, a1.clone(), a2.clone(), a3.clone()], &2);
+    client.set_spending_limit(&token_id, &1_000);
+
+    // Small withdrawals go straight through, large ones need a proposal
+    client.withdraw(&recipient, &token_id, &1_000);
+    assert_eq!(
+        client.try_withdraw(&recipient, &token_id, &1_001),
+        Err(Ok(QuipayError::ApprovalRequired))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(
+        client.try_propose_withdrawal(&outsider, &recipient, &token_id, &5_000, &1_000),
+        Err(Ok(QuipayError::NotApprover))
+    );
+    let id = client.propose_withdrawal(&a1, &recipient, &token_id, &5_000, &1_000);
+    assert_eq!(
+        client.try_execute_withdrawal(&id),
+        Err(Ok(QuipayError::InsufficientApprovals))
+    );
+    assert_eq!(
+        client.try_approve_withdrawal(&a1, &id),
+        Err(Ok(QuipayError::AlreadyApproved))
+    );
+
+    client.approve_withdrawal(&a3, &id);
+    client.execute_withdrawal(&id);
+
+    let proposal = client.get_withdrawal_proposal(&id).unwrap();
+    assert_eq!(proposal.status, ProposalStatus::Executed);
+    assert_eq!(proposal.approvals.len(), 2);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&recipient), 6_000);
+    assert_eq!(client.get_treasury_balance(&token_id), 4_000);
+    assert_eq!(
+        client.try_execute_withdrawal(&id),
+        Err(Ok(QuipayError::ProposalClosed))
+    );
+}
+
+#[test]
+fn test_spending_limit_is_cumulative_per_day() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let approver = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.set_approvers(&soroban_sdk::vec![&env, approver], &1);
+    client.set_spending_limit(&token_id, &1_000);
+
+    client.withdraw(&recipient, &token_id, &600);
+    assert_eq!(env.auths()[0].0, client.get_admin());
+
+    // Splitting a large withdrawal does not get around the limit
+    assert_eq!(
+        client.try_withdraw(&recipient, &token_id, &401),
+        Err(Ok(QuipayError::ApprovalRequired))
+    );
+    client.withdraw(&recipient, &token_id, &400);
+    assert_eq!(client.get_period_spent(&token_id), 1_000);
+    assert_eq!(
+        client.try_withdraw(&recipient, &token_id, &1),
+        Err(Ok(QuipayError::ApprovalRequired))
+    );
+
+    env.ledger().with_mut(|li| li.timestamp = 24 * 60 * 60);
+    assert_eq!(client.get_period_spent(&token_id), 0);
+    client.withdraw(&recipient, &token_id, &1_000);
+    assert_eq!(token::Client::new(&env, &token_id).balance(&recipient), 2_000);
+}
+
+#[test]
+fn test_withdrawal_proposal_expiry_and_cancellation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let a1 = Address::generate(&env);
+    let a2 = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.set_approvers(&soroban_sdk::vec![&env, a1.clone(), a2.clone()], &2);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    assert_eq!(
+        client.try_propose_withdrawal(&a1, &recipient, &token_id, &500, &100),
+        Err(Ok(QuipayError::ProposalExpired))
+    );
+
+    let expiring = client.propose_withdrawal(&a1, &recipient, &token_id, &500, &200);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    assert_eq!(
+        client.try_approve_withdrawal(&a2, &expiring),
+        Err(Ok(QuipayError::ProposalExpired))
+    );
+
+    let cancelled = client.propose_withdrawal(&a1, &recipient, &token_id, &500, &1_000);
+    assert_eq!(
+        client.try_cancel_withdrawal(&a2, &cancelled),
+        Err(Ok(QuipayError::Unauthorized))
+    );
+    client.cancel_withdrawal(&a1, &cancelled);
+    assert_eq!(
+        client.get_withdrawal_proposal(&cancelled).unwrap().status,
+        ProposalStatus::Cancelled
+    );
+    assert_eq!(
+        client.try_approve_withdrawal(&a2, &cancelled),
+        Err(Ok(QuipayError::ProposalClosed))
+    );
+}
+
+#[test]
+fn test_removed_approver_no_longer_counts() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, token_id, _, _) = setup_strategy(&env);
+    let a1 = Address::generate(&env);
+    let a2 = Address::generate(&env);
+    let a3 = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.set_approvers(&soroban_sdk::vec![&env, a1.clone(), a2.clone(), a3.clone()], &2);
+
+    let id = client.propose_withdrawal(&a1, &recipient, &token_id, &500, &1_000);
+    client.approve_withdrawal(&a2, &id);
+
+    // a1 rotated out before execution
+    client.set_approvers(&soroban_sdk::vec![&env, a2.clone(), a3.clone()], &2);
+    assert_eq!(
+        client.try_execute_withdrawal(&id),
+        Err(Ok(QuipayError::InsufficientApprovals))
+    );
+    client.approve_withdrawal(&a3, &id);
+    client.execute_withdrawal(&id);
+}
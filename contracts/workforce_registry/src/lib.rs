@@ -1,14 +1,103 @@
 #![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, symbol_short, Address, Env, String, Vec,
+    contract, contractimpl, contracttype, symbol_short, Address, BytesN, Env, IntoVal, Map,
+    String, Symbol, TryIntoVal, Val, Vec,
 };
 
+mod metadata;
+pub use metadata::{LegacyWorkerProfile, StorageScheme, WorkerMetadata};
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub struct WorkerProfile {
     pub wallet: Address,
     pub preferred_token: Address,
-    pub metadata_hash: String,
+    pub metadata: WorkerMetadata,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct EmployerProfile {
+    pub employer: Address,
+    pub name_hash: BytesN<32>,
+    pub metadata: String,
+    pub default_token: Address,
+    pub admins: Vec<Address>,
+    pub registered_at: u64,
+}
+
+/// One leg of a worker's payout split.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PayoutShare {
+    pub recipient: Address,
+    pub bps: u32,
+}
+
+/// A claim about a worker (e.g., KYC passed) vouched for by a trusted issuer.
+/// Only the hash of the underlying credential is stored on-chain.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attestation {
+    pub attestation_type: Symbol,
+    pub hash: BytesN<32>,
+    pub issuer: Address,
+    pub issued_at: u64,
+    pub expires_at: u64,
+}
+
+/// Employer-initiated recovery waiting out its timelock.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecoveryRequest {
+    pub new_wallet: Address,
+    pub employer: Address,
+    pub executable_at: u64,
+}
+
+const BPS_DENOMINATOR: u32 = 10_000;
+const RECOVERY_TIMELOCK_SECS: u64 = 7 * 24 * 60 * 60;
+const SECONDS_PER_MONTH: i128 = 30 * 24 * 60 * 60;
+const MAX_PAYOUT_SHARES: u32 = 10;
+
+/// Enumerable worker sets maintained for off-chain directory rebuilds.
+#[derive(Clone)]
+#[contracttype]
+pub enum WorkerIndex {
+    /// Every registered worker.
+    All,
+    /// Registered workers by preferred token.
+    Token(Address),
+    /// Every worker an employer has ever streamed to, including inactive ones.
+    EmployerHistory(Address),
+    /// Members of a team.
+    Team(u32),
+}
+
+/// An employer-owned group of workers with its own budget.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Team {
+    pub id: u32,
+    pub employer: Address,
+    pub name: String,
+    pub manager: Address,
+    pub budget_token: Address,
+    /// Maximum monthly burn (stream rate x 30 days) across the team's live streams.
+    pub monthly_cap: i128,
+}
+
+/// Aggregate spend of streams tagged with a team.
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TeamSpend {
+    pub active_streams: u32,
+    /// Sum of per-second rates of live tagged streams.
+    pub committed_rate: i128,
+    /// `committed_rate` over a 30-day month.
+    pub monthly_burn: i128,
+    /// Total paid out by tagged streams.
+    pub total_paid: i128,
 }
 
 #[derive(Clone)]
@@ -18,6 +107,30 @@ pub enum DataKey {
     EmployerActiveWorkerCount(Address),
     EmployerActiveWorkerByIndex(Address, u32),
     EmployerActiveWorkerIndex(Address, Address),
+    Employer(Address),
+    EmployerManager(Address, Address),
+    Admin,
+    StreamContract,
+    ActiveStreamCount(Address, Address),
+    PayoutRoute(Address),
+    /// Lowest swap rate a worker accepts into their preferred token, per stream token.
+    SwapFloors(Address),
+    WorkerLiveStreams(Address),
+    Guardian(Address),
+    Recovery(Address),
+    /// New wallet and stream index slot a paged recovery resumes from.
+    StreamMigration(Address),
+    Issuer(Address),
+    Attestation(Address, Symbol),
+    AttestationTypes(Address),
+    IndexCount(WorkerIndex),
+    IndexEntry(WorkerIndex, u32),
+    IndexPosition(WorkerIndex, Address),
+    NextTeamId,
+    Team(u32),
+    EmployerTeams(Address),
+    WorkerTeams(Address),
+    TeamSpend(u32),
 }
 
 #[contract]
@@ -31,12 +144,13 @@ impl WorkforceRegistryContract {
     /// * `e` - The environment.
     /// * `worker` - The address of the worker registering.
     /// * `preferred_token` - The address of the preferred payment token.
-    /// * `metadata_hash` - A hash string pointing to metadata (e.g., IPFS/Arweave).
+    /// * `metadata` - Pointer to the worker's off-chain metadata. `updated_at` is
+    ///   set by the registry.
     pub fn register_worker(
         e: Env,
         worker: Address,
         preferred_token: Address,
-        metadata_hash: String,
+        metadata: WorkerMetadata,
     ) {
         worker.require_auth();
         
@@ -48,19 +162,22 @@ impl WorkforceRegistryContract {
         let profile = WorkerProfile {
             wallet: worker.clone(),
             preferred_token: preferred_token.clone(),
-            metadata_hash: metadata_hash.clone(),
+            metadata: Self::stamp_metadata(&e, metadata),
         };
         
         e.storage().persistent().set(&key, &profile);
+        Self::index_insert(&e, WorkerIndex::All, &worker);
+        Self::index_insert(&e, WorkerIndex::Token(preferred_token.clone()), &worker);
 
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("registry"),
-                symbol_short!("registered"),
+                Symbol::new(&e, "registered"),
                 worker.clone(),
                 preferred_token.clone(),
             ),
-            (metadata_hash.clone()),
+            profile.metadata,
         );
     }
 
@@ -70,28 +187,36 @@ impl WorkforceRegistryContract {
     /// * `e` - The environment.
     /// * `worker` - The address of the worker updating their profile.
     /// * `preferred_token` - The new preferred payment token address.
-    /// * `metadata_hash` - The new metadata hash string.
+    /// * `metadata` - The new metadata pointer. `updated_at` is set by the registry.
     pub fn update_worker(
         e: Env,
         worker: Address,
         preferred_token: Address,
-        metadata_hash: String,
+        metadata: WorkerMetadata,
     ) {
         worker.require_auth();
         
-        let key = DataKey::Worker(worker.clone());
-        if !e.storage().persistent().has(&key) {
-            panic!("Worker not registered");
-        }
+        let previous = Self::load_worker(&e, &worker).expect("Worker not registered");
         
         let profile = WorkerProfile {
             wallet: worker.clone(),
             preferred_token: preferred_token.clone(),
-            metadata_hash: metadata_hash.clone(),
+            metadata: Self::stamp_metadata(&e, metadata),
         };
         
-        e.storage().persistent().set(&key, &profile);
+        e.storage().persistent().set(&DataKey::Worker(worker.clone()), &profile);
+        if previous.preferred_token != preferred_token {
+            Self::index_remove(&e, WorkerIndex::Token(previous.preferred_token), &worker);
+            // Floors are quoted in the old token
+            e.storage()
+                .persistent()
+                .remove(&DataKey::SwapFloors(worker.clone()));
+        }
+        // Legacy registrations predate the worker indexes; inserts are idempotent
+        Self::index_insert(&e, WorkerIndex::All, &worker);
+        Self::index_insert(&e, WorkerIndex::Token(preferred_token.clone()), &worker);
 
+        #[allow(deprecated)]
         e.events().publish(
             (
                 symbol_short!("registry"),
@@ -99,10 +224,44 @@ impl WorkforceRegistryContract {
                 worker.clone(),
                 preferred_token.clone(),
             ),
-            (metadata_hash),
+            profile.metadata,
         );
     }
 
+    /// Rewrites worker records stored in the pre-typed-metadata layout.
+    /// Converted records get schema version 0 and a content hash of the old
+    /// string until the worker supplies real metadata via `update_worker`.
+    /// Safe for anyone to call; already-migrated workers are skipped.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `workers` - Worker addresses to migrate.
+    ///
+    /// # Returns
+    /// * `u32` - The number of records migrated.
+    pub fn migrate_workers(e: Env, workers: Vec<Address>) -> u32 {
+        let mut migrated: u32 = 0;
+        for worker in workers.iter() {
+            if !Self::is_legacy(&e, &worker) {
+                continue;
+            }
+            let profile = Self::load_worker(&e, &worker).unwrap();
+            e.storage()
+                .persistent()
+                .set(&DataKey::Worker(worker.clone()), &profile);
+            // Legacy registrations predate the worker indexes
+            Self::index_insert(&e, WorkerIndex::All, &worker);
+            Self::index_insert(&e, WorkerIndex::Token(profile.preferred_token), &worker);
+            migrated += 1;
+        }
+        migrated
+    }
+
+    /// Checks whether a worker record still uses the legacy layout.
+    pub fn needs_migration(e: Env, worker: Address) -> bool {
+        Self::is_legacy(&e, &worker)
+    }
+
     /// Retrieves a worker's profile.
     /// 
     /// # Arguments
@@ -112,8 +271,162 @@ impl WorkforceRegistryContract {
     /// # Returns
     /// * `Option<WorkerProfile>` - The worker profile if found, None otherwise.
     pub fn get_worker(e: Env, worker: Address) -> Option<WorkerProfile> {
-        let key = DataKey::Worker(worker);
-        e.storage().persistent().get(&key)
+        Self::load_worker(&e, &worker)
+    }
+
+    /// Retrieves the token a worker wants to be paid in.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker address.
+    ///
+    /// # Returns
+    /// * `Option<Address>` - The preferred token if the worker is registered.
+    pub fn get_preferred_token(e: Env, worker: Address) -> Option<Address> {
+        Self::load_worker(&e, &worker).map(|p| p.preferred_token)
+    }
+
+    /// Routes a worker's salary to one or more payout addresses instead of their wallet.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The registered worker configuring their payouts.
+    /// * `shares` - Recipient/basis-point pairs; must be non-empty, positive,
+    ///   free of duplicates and sum to 10000.
+    pub fn set_payout_route(e: Env, worker: Address, shares: Vec<PayoutShare>) {
+        worker.require_auth();
+
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        if shares.is_empty() || shares.len() > MAX_PAYOUT_SHARES {
+            panic!("Invalid number of payout shares");
+        }
+
+        let mut total: u32 = 0;
+        let mut i: u32 = 0;
+        while i < shares.len() {
+            let share = shares.get(i).unwrap();
+            if share.bps == 0 {
+                panic!("Payout share must be positive");
+            }
+            let mut j: u32 = 0;
+            while j < i {
+                if shares.get(j).unwrap().recipient == share.recipient {
+                    panic!("Duplicate payout recipient");
+                }
+                j += 1;
+            }
+            total = total.saturating_add(share.bps);
+            i += 1;
+        }
+        if total != BPS_DENOMINATOR {
+            panic!("Payout shares must sum to 10000");
+        }
+
+        e.storage()
+            .persistent()
+            .set(&DataKey::PayoutRoute(worker.clone()), &shares);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("payout"), worker),
+            shares,
+        );
+    }
+
+    /// Removes a worker's payout route so salary goes to their wallet again.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker clearing their payout route.
+    pub fn clear_payout_route(e: Env, worker: Address) {
+        worker.require_auth();
+        e.storage()
+            .persistent()
+            .remove(&DataKey::PayoutRoute(worker.clone()));
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("payout"), worker),
+            Vec::<PayoutShare>::new(&e),
+        );
+    }
+
+    /// Retrieves a worker's payout route.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker address.
+    ///
+    /// # Returns
+    /// * `Vec<PayoutShare>` - The configured shares, or empty when payouts go to the wallet.
+    pub fn get_payout_route(e: Env, worker: Address) -> Vec<PayoutShare> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::PayoutRoute(worker))
+            .unwrap_or_else(|| Vec::new(&e))
+    }
+
+    /// Sets the lowest rate at which the worker accepts swapped payouts from
+    /// `token_in` into their preferred token, as output per input in basis points
+    /// (e.g. 19500 = at least 1.95 preferred units per stream unit). Payouts
+    /// without a floor, or whose quote falls below it, are paid in `token_in`.
+    /// Floors are cleared when the preferred token changes.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The registered worker configuring the floor.
+    /// * `token_in` - The stream token being swapped.
+    /// * `min_rate_bps` - The floor; 0 removes it.
+    pub fn set_swap_floor(e: Env, worker: Address, token_in: Address, min_rate_bps: i128) {
+        worker.require_auth();
+
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        if min_rate_bps < 0 {
+            panic!("Swap floor must not be negative");
+        }
+
+        let key = DataKey::SwapFloors(worker.clone());
+        let mut floors: Map<Address, i128> = e
+            .storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| Map::new(&e));
+        if min_rate_bps == 0 {
+            floors.remove(token_in.clone());
+        } else {
+            floors.set(token_in.clone(), min_rate_bps);
+        }
+        if floors.is_empty() {
+            e.storage().persistent().remove(&key);
+        } else {
+            e.storage().persistent().set(&key, &floors);
+        }
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("swapfloor"), worker),
+            (token_in, min_rate_bps),
+        );
+    }
+
+    /// Retrieves a worker's swap floor for `token_in`.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker address.
+    /// * `token_in` - The stream token being swapped.
+    ///
+    /// # Returns
+    /// * `Option<i128>` - The floor in basis points, or None when swaps are not accepted.
+    pub fn get_swap_floor(e: Env, worker: Address, token_in: Address) -> Option<i128> {
+        e.storage()
+            .persistent()
+            .get::<DataKey, Map<Address, i128>>(&DataKey::SwapFloors(worker))
+            .and_then(|floors| floors.get(token_in))
     }
 
     /// Checks if a worker is registered.
@@ -129,115 +442,1423 @@ impl WorkforceRegistryContract {
         e.storage().persistent().has(&key)
     }
 
-    pub fn set_stream_active(e: Env, employer: Address, worker: Address, active: bool) {
+    /// Removes a worker profile. Blocked while the worker has live streams.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker deregistering.
+    pub fn deregister_worker(e: Env, worker: Address) {
+        worker.require_auth();
+
+        let key = DataKey::Worker(worker.clone());
+        let profile = Self::load_worker(&e, &worker).expect("Worker not registered");
+        if Self::get_live_stream_count(e.clone(), worker.clone()) > 0 {
+            panic!("Worker has active streams");
+        }
+
+        e.storage().persistent().remove(&key);
+        e.storage().persistent().remove(&DataKey::PayoutRoute(worker.clone()));
+        e.storage().persistent().remove(&DataKey::SwapFloors(worker.clone()));
+        e.storage().persistent().remove(&DataKey::Guardian(worker.clone()));
+        e.storage().persistent().remove(&DataKey::Recovery(worker.clone()));
+        let types_key = DataKey::AttestationTypes(worker.clone());
+        let types: Vec<Symbol> = e
+            .storage()
+            .persistent()
+            .get(&types_key)
+            .unwrap_or_else(|| Vec::new(&e));
+        for attestation_type in types.iter() {
+            e.storage()
+                .persistent()
+                .remove(&DataKey::Attestation(worker.clone(), attestation_type));
+        }
+        e.storage().persistent().remove(&types_key);
+
+        Self::index_remove(&e, WorkerIndex::All, &worker);
+        Self::index_remove(&e, WorkerIndex::Token(profile.preferred_token), &worker);
+        for team_id in Self::get_worker_teams(e.clone(), worker.clone()).iter() {
+            Self::leave_team(&e, team_id, &worker);
+        }
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), Symbol::new(&e, "deregistered"), worker),
+            (),
+        );
+    }
+
+    /// Total live streams paying a worker across all employers.
+    pub fn get_live_stream_count(e: Env, worker: Address) -> u32 {
+        e.storage()
+            .persistent()
+            .get(&DataKey::WorkerLiveStreams(worker))
+            .unwrap_or(0)
+    }
+
+    /// Designates an address that can move the worker's profile to a new wallet
+    /// without delay if the worker loses their key.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The registered worker.
+    /// * `guardian` - The recovery guardian.
+    pub fn set_guardian(e: Env, worker: Address, guardian: Address) {
+        worker.require_auth();
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        e.storage()
+            .persistent()
+            .set(&DataKey::Guardian(worker), &guardian);
+    }
+
+    /// Removes the worker's recovery guardian.
+    pub fn remove_guardian(e: Env, worker: Address) {
+        worker.require_auth();
+        e.storage().persistent().remove(&DataKey::Guardian(worker));
+    }
+
+    /// Retrieves the worker's recovery guardian.
+    pub fn get_guardian(e: Env, worker: Address) -> Option<Address> {
+        e.storage().persistent().get(&DataKey::Guardian(worker))
+    }
+
+    /// Immediately migrates a worker to `new_wallet` on the guardian's authority.
+    /// The new wallet must also authorize, proving it is controlled.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker address being recovered.
+    /// * `new_wallet` - The unregistered address taking over the profile.
+    pub fn recover_worker(e: Env, worker: Address, new_wallet: Address) {
+        let guardian: Address = e
+            .storage()
+            .persistent()
+            .get(&DataKey::Guardian(worker.clone()))
+            .expect("No guardian set");
+        guardian.require_auth();
+        new_wallet.require_auth();
+
+        Self::migrate_worker(&e, &worker, &new_wallet);
+    }
+
+    /// Starts an employer-initiated recovery. It can be executed once the
+    /// timelock passes, giving the worker time to cancel it if they still hold their key.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - A registered employer with a live stream to the worker.
+    /// * `worker` - The worker address being recovered.
+    /// * `new_wallet` - The unregistered address taking over the profile.
+    pub fn initiate_recovery(e: Env, employer: Address, worker: Address, new_wallet: Address) {
         employer.require_auth();
+        Self::load_employer(&e, &employer);
+
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        if Self::get_active_stream_count(e.clone(), employer.clone(), worker.clone()) == 0 {
+            panic!("Employer does not pay worker");
+        }
+        if e.storage().persistent().has(&DataKey::Worker(new_wallet.clone())) {
+            panic!("Worker already registered");
+        }
+
+        let request = RecoveryRequest {
+            new_wallet,
+            employer,
+            executable_at: e.ledger().timestamp() + RECOVERY_TIMELOCK_SECS,
+        };
+        e.storage()
+            .persistent()
+            .set(&DataKey::Recovery(worker.clone()), &request);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("recovery"), worker),
+            request,
+        );
+    }
+
+    /// Completes an employer-initiated recovery after its timelock.
+    /// The new wallet must authorize.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker address being recovered.
+    pub fn execute_recovery(e: Env, worker: Address) {
+        let request: RecoveryRequest = e
+            .storage()
+            .persistent()
+            .get(&DataKey::Recovery(worker.clone()))
+            .expect("No pending recovery");
+        if e.ledger().timestamp() < request.executable_at {
+            panic!("Recovery timelock active");
+        }
+        request.new_wallet.require_auth();
+
+        Self::migrate_worker(&e, &worker, &request.new_wallet);
+    }
+
+    /// Continues repointing a recovered worker's streams when the recovery moved
+    /// more than one page. Anyone may call it until it returns false.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The recovered (old) worker address.
+    ///
+    /// # Returns
+    /// * `bool` - True while streams are still left to move.
+    pub fn resume_stream_migration(e: Env, worker: Address) -> bool {
+        let (new_wallet, cursor): (Address, u32) = e
+            .storage()
+            .persistent()
+            .get(&DataKey::StreamMigration(worker.clone()))
+            .expect("No pending stream migration");
+        Self::migrate_streams(&e, &worker, &new_wallet, cursor)
+    }
+
+    /// Cancels a pending recovery. Callable by the worker or the initiating employer.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The worker or the employer that initiated the recovery.
+    /// * `worker` - The worker address the recovery targets.
+    pub fn cancel_recovery(e: Env, caller: Address, worker: Address) {
+        caller.require_auth();
+        let key = DataKey::Recovery(worker.clone());
+        let request: RecoveryRequest = e
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("No pending recovery");
+        if caller != worker && caller != request.employer {
+            panic!("Not authorized to cancel recovery");
+        }
+        e.storage().persistent().remove(&key);
+    }
+
+    /// Retrieves a worker's pending recovery request.
+    pub fn get_recovery(e: Env, worker: Address) -> Option<RecoveryRequest> {
+        e.storage().persistent().get(&DataKey::Recovery(worker))
+    }
+
+    /// Initializes the registry with an admin.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `admin` - The address allowed to configure the registry.
+    pub fn init(e: Env, admin: Address) {
+        if e.storage().instance().has(&DataKey::Admin) {
+            panic!("Already initialized");
+        }
+        e.storage().instance().set(&DataKey::Admin, &admin);
+    }
+
+    /// Sets the PayrollStream contract whose notifications maintain the active-worker set.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `stream_contract` - The PayrollStream contract address.
+    pub fn set_stream_contract(e: Env, stream_contract: Address) {
+        Self::require_admin(&e);
+        e.storage()
+            .instance()
+            .set(&DataKey::StreamContract, &stream_contract);
+    }
+
+    /// Retrieves the configured PayrollStream contract.
+    pub fn get_stream_contract(e: Env) -> Option<Address> {
+        e.storage().instance().get(&DataKey::StreamContract)
+    }
+
+    /// Records a newly created stream between an employer and a worker.
+    /// The worker joins the employer's active set when its first live stream opens.
+    /// Only the configured stream contract can call this.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The registered employer paying the worker.
+    /// * `worker` - The registered worker.
+    pub fn stream_opened(e: Env, employer: Address, worker: Address) {
+        Self::require_stream_contract(&e);
+        Self::load_employer(&e, &employer);
 
         let worker_key = DataKey::Worker(worker.clone());
         if !e.storage().persistent().has(&worker_key) {
             panic!("Worker not registered");
         }
 
-        let idx_key = DataKey::EmployerActiveWorkerIndex(employer.clone(), worker.clone());
-        let is_active = e.storage().persistent().has(&idx_key);
+        Self::open_ref(&e, &employer, &worker);
+    }
 
-        if active {
-            if is_active {
-                return;
-            }
+    /// Records that a stream was canceled or completed. The worker leaves the
+    /// employer's active set once no live streams remain. Never fails for
+    /// untracked pairs so it cannot block a cancellation.
+    /// Only the configured stream contract can call this.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The employer paying the worker.
+    /// * `worker` - The worker.
+    pub fn stream_closed(e: Env, employer: Address, worker: Address) {
+        Self::require_stream_contract(&e);
+        Self::close_ref(&e, &employer, &worker);
+    }
 
-            let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
-            let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
-
-            let by_index_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), count);
-            e.storage().persistent().set(&by_index_key, &worker);
-
-            let stored_index: u32 = count + 1;
-            e.storage().persistent().set(&idx_key, &stored_index);
-            e.storage().persistent().set(&count_key, &(count + 1));
-
-            e.events().publish(
-                (
-                    symbol_short!("stream"),
-                    symbol_short!("active"),
-                    employer.clone(),
-                    worker.clone(),
-                ),
-                (),
-            );
-        } else {
-            if !is_active {
-                return;
-            }
+    /// Number of live streams between an employer and a worker.
+    pub fn get_active_stream_count(e: Env, employer: Address, worker: Address) -> u32 {
+        e.storage()
+            .persistent()
+            .get(&DataKey::ActiveStreamCount(employer, worker))
+            .unwrap_or(0)
+    }
 
-            let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
-            let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
-            if count == 0 {
-                e.storage().persistent().remove(&idx_key);
-                return;
-            }
+    /// Trusts an issuer to attach attestations to workers.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `issuer` - The issuer address (e.g., a KYC provider).
+    pub fn add_issuer(e: Env, issuer: Address) {
+        Self::require_admin(&e);
+        e.storage().persistent().set(&DataKey::Issuer(issuer.clone()), &true);
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("issuer"), issuer),
+            true,
+        );
+    }
+
+    /// Revokes trust in an issuer. Its attestations stop counting as valid.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `issuer` - The issuer address.
+    pub fn remove_issuer(e: Env, issuer: Address) {
+        Self::require_admin(&e);
+        e.storage().persistent().remove(&DataKey::Issuer(issuer.clone()));
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("issuer"), issuer),
+            false,
+        );
+    }
 
-            let stored_index: u32 = e.storage().persistent().get(&idx_key).unwrap();
-            let remove_pos: u32 = stored_index - 1;
-            let last_pos: u32 = count - 1;
+    /// Checks whether an issuer is trusted.
+    pub fn is_issuer(e: Env, issuer: Address) -> bool {
+        e.storage().persistent().has(&DataKey::Issuer(issuer))
+    }
 
-            if remove_pos != last_pos {
-                let last_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), last_pos);
-                let last_worker: Address = e.storage().persistent().get(&last_key).unwrap();
+    /// Attaches an attestation to a worker, replacing any earlier one of the same type.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `issuer` - A trusted issuer; its authorization signs the attestation.
+    /// * `worker` - The registered worker.
+    /// * `attestation_type` - What is attested (e.g., `KYC`).
+    /// * `hash` - Hash of the off-chain credential.
+    /// * `expires_at` - Ledger timestamp after which the attestation is invalid.
+    pub fn attest(
+        e: Env,
+        issuer: Address,
+        worker: Address,
+        attestation_type: Symbol,
+        hash: BytesN<32>,
+        expires_at: u64,
+    ) {
+        issuer.require_auth();
+        if !Self::is_issuer(e.clone(), issuer.clone()) {
+            panic!("Issuer not trusted");
+        }
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        let now = e.ledger().timestamp();
+        if expires_at <= now {
+            panic!("Attestation already expired");
+        }
 
-                let remove_key =
-                    DataKey::EmployerActiveWorkerByIndex(employer.clone(), remove_pos);
-                e.storage().persistent().set(&remove_key, &last_worker);
+        let attestation = Attestation {
+            attestation_type: attestation_type.clone(),
+            hash,
+            issuer,
+            issued_at: now,
+            expires_at,
+        };
+        e.storage().persistent().set(
+            &DataKey::Attestation(worker.clone(), attestation_type.clone()),
+            &attestation,
+        );
 
-                let last_worker_idx_key =
-                    DataKey::EmployerActiveWorkerIndex(employer.clone(), last_worker.clone());
-                e.storage().persistent().set(&last_worker_idx_key, &(remove_pos + 1));
+        let types_key = DataKey::AttestationTypes(worker.clone());
+        let mut types: Vec<Symbol> = e
+            .storage()
+            .persistent()
+            .get(&types_key)
+            .unwrap_or_else(|| Vec::new(&e));
+        if !types.contains(&attestation_type) {
+            types.push_back(attestation_type);
+            e.storage().persistent().set(&types_key, &types);
+        }
 
-                e.storage().persistent().remove(&last_key);
-            } else {
-                let last_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), last_pos);
-                e.storage().persistent().remove(&last_key);
-            }
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("attested"), worker),
+            attestation,
+        );
+    }
 
-            e.storage().persistent().remove(&idx_key);
-            e.storage().persistent().set(&count_key, &(count - 1));
+    /// Removes a worker's attestation. Callable by its issuer or the admin.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The attestation's issuer or the registry admin.
+    /// * `worker` - The worker.
+    /// * `attestation_type` - The attestation type to remove.
+    pub fn revoke_attestation(e: Env, caller: Address, worker: Address, attestation_type: Symbol) {
+        caller.require_auth();
+        let key = DataKey::Attestation(worker.clone(), attestation_type.clone());
+        let attestation: Attestation = e
+            .storage()
+            .persistent()
+            .get(&key)
+            .expect("Attestation not found");
+        let admin: Option<Address> = e.storage().instance().get(&DataKey::Admin);
+        if caller != attestation.issuer && Some(caller) != admin {
+            panic!("Not authorized to revoke attestation");
+        }
 
-            e.events().publish(
-                (
-                    symbol_short!("stream"),
-                    symbol_short!("inactive"),
-                    employer.clone(),
-                    worker.clone(),
-                ),
-                (),
-            );
+        e.storage().persistent().remove(&key);
+        let types_key = DataKey::AttestationTypes(worker.clone());
+        let mut types: Vec<Symbol> = e
+            .storage()
+            .persistent()
+            .get(&types_key)
+            .unwrap_or_else(|| Vec::new(&e));
+        if let Some(idx) = types.first_index_of(&attestation_type) {
+            types.remove(idx);
+            e.storage().persistent().set(&types_key, &types);
         }
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("registry"), symbol_short!("revoked"), worker),
+            attestation_type,
+        );
     }
 
-    pub fn get_workers_by_employer(e: Env, employer: Address, start: u32, limit: u32) -> Vec<WorkerProfile> {
-        let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
-        let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
+    /// Retrieves a worker's attestation of the given type, valid or not.
+    pub fn get_attestation(e: Env, worker: Address, attestation_type: Symbol) -> Option<Attestation> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Attestation(worker, attestation_type))
+    }
 
-        if start >= count || limit == 0 {
-            return Vec::new(&e);
+    /// Checks whether a worker holds an unexpired attestation of every listed
+    /// type, each from a still-trusted issuer.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `worker` - The worker.
+    /// * `attestation_types` - The required attestation types.
+    ///
+    /// # Returns
+    /// * `bool` - True if all requirements are met.
+    pub fn has_attestations(e: Env, worker: Address, attestation_types: Vec<Symbol>) -> bool {
+        let now = e.ledger().timestamp();
+        for attestation_type in attestation_types.iter() {
+            let attestation: Option<Attestation> = e
+                .storage()
+                .persistent()
+                .get(&DataKey::Attestation(worker.clone(), attestation_type));
+            match attestation {
+                Some(a) if a.expires_at > now && Self::is_issuer(e.clone(), a.issuer.clone()) => {}
+                _ => return false,
+            }
         }
+        true
+    }
 
-        let end_exclusive = if start.saturating_add(limit) > count {
-            count
-        } else {
-            start + limit
-        };
+    /// Registers an employer profile.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The address of the employer registering.
+    /// * `name_hash` - Hash of the employer's legal name (kept off-chain).
+    /// * `metadata` - Pointer to additional off-chain metadata (e.g., IPFS CID).
+    /// * `default_token` - The token the employer normally pays in.
+    /// * `admins` - Addresses allowed to manage the profile alongside the employer.
+    pub fn register_employer(
+        e: Env,
+        employer: Address,
+        name_hash: BytesN<32>,
+        metadata: String,
+        default_token: Address,
+        admins: Vec<Address>,
+    ) {
+        employer.require_auth();
 
-        let mut out: Vec<WorkerProfile> = Vec::new(&e);
-        let mut i = start;
-        while i < end_exclusive {
-            let by_index_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), i);
-            let worker: Address = e.storage().persistent().get(&by_index_key).unwrap();
-            let worker_key = DataKey::Worker(worker);
-            let profile: WorkerProfile = e.storage().persistent().get(&worker_key).unwrap();
-            out.push_back(profile);
-            i += 1;
+        let key = DataKey::Employer(employer.clone());
+        if e.storage().persistent().has(&key) {
+            panic!("Employer already registered");
         }
 
+        let profile = EmployerProfile {
+            employer: employer.clone(),
+            name_hash: name_hash.clone(),
+            metadata,
+            default_token: default_token.clone(),
+            admins,
+            registered_at: e.ledger().timestamp(),
+        };
+        e.storage().persistent().set(&key, &profile);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("employer"),
+                Symbol::new(&e, "registered"),
+                employer,
+                default_token,
+            ),
+            name_hash,
+        );
+    }
+
+    /// Updates an employer profile.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The employer or one of its admins.
+    /// * `employer` - The employer whose profile is updated.
+    /// * `name_hash` - The new name hash.
+    /// * `metadata` - The new metadata pointer.
+    /// * `default_token` - The new default payroll token.
+    /// * `admins` - The new admin list.
+    pub fn update_employer(
+        e: Env,
+        caller: Address,
+        employer: Address,
+        name_hash: BytesN<32>,
+        metadata: String,
+        default_token: Address,
+        admins: Vec<Address>,
+    ) {
+        caller.require_auth();
+        let mut profile = Self::require_employer_admin(&e, &employer, &caller);
+
+        profile.name_hash = name_hash.clone();
+        profile.metadata = metadata;
+        profile.default_token = default_token.clone();
+        profile.admins = admins;
+        e.storage()
+            .persistent()
+            .set(&DataKey::Employer(employer.clone()), &profile);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("employer"),
+                symbol_short!("updated"),
+                employer,
+                default_token,
+            ),
+            name_hash,
+        );
+    }
+
+    /// Retrieves an employer's profile.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The address of the employer to look up.
+    ///
+    /// # Returns
+    /// * `Option<EmployerProfile>` - The employer profile if found, None otherwise.
+    pub fn get_employer(e: Env, employer: Address) -> Option<EmployerProfile> {
+        e.storage().persistent().get(&DataKey::Employer(employer))
+    }
+
+    /// Checks if an employer is registered.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The address of the employer to check.
+    ///
+    /// # Returns
+    /// * `bool` - True if registered, False otherwise.
+    pub fn is_employer_registered(e: Env, employer: Address) -> bool {
+        e.storage().persistent().has(&DataKey::Employer(employer))
+    }
+
+    /// Allows `manager` to act on the employer's behalf.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The employer or one of its admins.
+    /// * `employer` - The employer granting the permission.
+    /// * `manager` - The sub-manager address.
+    pub fn add_manager(e: Env, caller: Address, employer: Address, manager: Address) {
+        caller.require_auth();
+        Self::require_employer_admin(&e, &employer, &caller);
+
+        e.storage()
+            .persistent()
+            .set(&DataKey::EmployerManager(employer.clone(), manager.clone()), &true);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("employer"),
+                symbol_short!("mgr_add"),
+                employer,
+                manager,
+            ),
+            (),
+        );
+    }
+
+    /// Revokes a sub-manager.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The employer or one of its admins.
+    /// * `employer` - The employer revoking the permission.
+    /// * `manager` - The sub-manager address.
+    pub fn remove_manager(e: Env, caller: Address, employer: Address, manager: Address) {
+        caller.require_auth();
+        Self::require_employer_admin(&e, &employer, &caller);
+
+        e.storage()
+            .persistent()
+            .remove(&DataKey::EmployerManager(employer.clone(), manager.clone()));
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("employer"),
+                symbol_short!("mgr_rem"),
+                employer,
+                manager,
+            ),
+            (),
+        );
+    }
+
+    /// Checks if an address may act on an employer's behalf.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The employer.
+    /// * `actor` - The address to check.
+    ///
+    /// # Returns
+    /// * `bool` - True for the employer itself, its admins and its managers.
+    pub fn can_act_for(e: Env, employer: Address, actor: Address) -> bool {
+        match Self::get_employer(e.clone(), employer.clone()) {
+            Some(profile) => Self::is_delegate(&e, &profile, &actor),
+            None => false,
+        }
+    }
+
+    /// Creates a team owned by an employer.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The employer or one of its admins.
+    /// * `employer` - The owning employer.
+    /// * `name` - Display name of the team.
+    /// * `manager` - Address allowed to manage membership.
+    /// * `budget_token` - Token the team's streams must pay in.
+    /// * `monthly_cap` - Maximum monthly burn of the team's live streams.
+    ///
+    /// # Returns
+    /// * `u32` - The new team id.
+    pub fn create_team(
+        e: Env,
+        caller: Address,
+        employer: Address,
+        name: String,
+        manager: Address,
+        budget_token: Address,
+        monthly_cap: i128,
+    ) -> u32 {
+        caller.require_auth();
+        Self::require_employer_admin(&e, &employer, &caller);
+        if monthly_cap <= 0 {
+            panic!("Monthly cap must be positive");
+        }
+
+        let id: u32 = e.storage().instance().get(&DataKey::NextTeamId).unwrap_or(1);
+        e.storage().instance().set(&DataKey::NextTeamId, &(id + 1));
+
+        let team = Team {
+            id,
+            employer: employer.clone(),
+            name,
+            manager,
+            budget_token,
+            monthly_cap,
+        };
+        e.storage().persistent().set(&DataKey::Team(id), &team);
+
+        let teams_key = DataKey::EmployerTeams(employer.clone());
+        let mut teams: Vec<u32> = e
+            .storage()
+            .persistent()
+            .get(&teams_key)
+            .unwrap_or_else(|| Vec::new(&e));
+        teams.push_back(id);
+        e.storage().persistent().set(&teams_key, &teams);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("team"), symbol_short!("created"), employer, id),
+            team,
+        );
+        id
+    }
+
+    /// Updates a team's name, manager and budget. Lowering the cap below the
+    /// current burn only blocks new streams.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - The employer or one of its admins.
+    /// * `team_id` - The team.
+    /// * `name` - New display name.
+    /// * `manager` - New team manager.
+    /// * `budget_token` - New budget token.
+    /// * `monthly_cap` - New monthly cap.
+    pub fn update_team(
+        e: Env,
+        caller: Address,
+        team_id: u32,
+        name: String,
+        manager: Address,
+        budget_token: Address,
+        monthly_cap: i128,
+    ) {
+        caller.require_auth();
+        let mut team = Self::load_team(&e, team_id);
+        Self::require_employer_admin(&e, &team.employer, &caller);
+        if monthly_cap <= 0 {
+            panic!("Monthly cap must be positive");
+        }
+        if budget_token != team.budget_token && Self::get_team_spend(e.clone(), team_id).active_streams > 0 {
+            panic!("Team has active streams");
+        }
+
+        team.name = name;
+        team.manager = manager;
+        team.budget_token = budget_token;
+        team.monthly_cap = monthly_cap;
+        e.storage().persistent().set(&DataKey::Team(team_id), &team);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("team"), symbol_short!("updated"), team.employer.clone(), team_id),
+            team,
+        );
+    }
+
+    /// Retrieves a team.
+    pub fn get_team(e: Env, team_id: u32) -> Option<Team> {
+        e.storage().persistent().get(&DataKey::Team(team_id))
+    }
+
+    /// Ids of every team an employer has created.
+    pub fn get_employer_teams(e: Env, employer: Address) -> Vec<u32> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::EmployerTeams(employer))
+            .unwrap_or_else(|| Vec::new(&e))
+    }
+
+    /// Adds a registered worker to a team.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - An employer admin or sub-manager, or the team manager.
+    /// * `team_id` - The team.
+    /// * `worker` - The registered worker.
+    pub fn add_team_member(e: Env, caller: Address, team_id: u32, worker: Address) {
+        caller.require_auth();
+        let team = Self::load_team(&e, team_id);
+        Self::require_team_manager(&e, &team, &caller);
+        if !e.storage().persistent().has(&DataKey::Worker(worker.clone())) {
+            panic!("Worker not registered");
+        }
+        if Self::is_team_member(e.clone(), team_id, worker.clone()) {
+            return;
+        }
+
+        Self::index_insert(&e, WorkerIndex::Team(team_id), &worker);
+        let teams_key = DataKey::WorkerTeams(worker.clone());
+        let mut teams: Vec<u32> = e
+            .storage()
+            .persistent()
+            .get(&teams_key)
+            .unwrap_or_else(|| Vec::new(&e));
+        teams.push_back(team_id);
+        e.storage().persistent().set(&teams_key, &teams);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("team"), symbol_short!("joined"), team_id, worker),
+            (),
+        );
+    }
+
+    /// Removes a worker from a team. Streams already tagged with the team keep running.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `caller` - An employer admin or sub-manager, or the team manager.
+    /// * `team_id` - The team.
+    /// * `worker` - The member to remove.
+    pub fn remove_team_member(e: Env, caller: Address, team_id: u32, worker: Address) {
+        caller.require_auth();
+        let team = Self::load_team(&e, team_id);
+        Self::require_team_manager(&e, &team, &caller);
+        Self::leave_team(&e, team_id, &worker);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (symbol_short!("team"), symbol_short!("left"), team_id, worker),
+            (),
+        );
+    }
+
+    /// Checks whether a worker belongs to a team.
+    pub fn is_team_member(e: Env, team_id: u32, worker: Address) -> bool {
+        e.storage()
+            .persistent()
+            .has(&DataKey::IndexPosition(WorkerIndex::Team(team_id), worker))
+    }
+
+    /// Number of members in a team.
+    pub fn get_team_member_count(e: Env, team_id: u32) -> u32 {
+        Self::index_count(&e, WorkerIndex::Team(team_id))
+    }
+
+    /// Paginates over a team's members.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `team_id` - The team.
+    /// * `start` - Index of the first member to return.
+    /// * `limit` - Maximum number of members to return.
+    ///
+    /// # Returns
+    /// * `Vec<WorkerProfile>` - The page of member profiles.
+    pub fn get_team_members(e: Env, team_id: u32, start: u32, limit: u32) -> Vec<WorkerProfile> {
+        Self::profiles(&e, Self::index_page(&e, WorkerIndex::Team(team_id), start, limit))
+    }
+
+    /// Ids of the teams a worker belongs to.
+    pub fn get_worker_teams(e: Env, worker: Address) -> Vec<u32> {
+        e.storage()
+            .persistent()
+            .get(&DataKey::WorkerTeams(worker))
+            .unwrap_or_else(|| Vec::new(&e))
+    }
+
+    /// Records a stream tagged with a team. Panics unless the stream belongs to
+    /// the team's employer, pays a member in the budget token, and keeps the
+    /// team's monthly burn within its cap.
+    /// Only the configured stream contract can call this.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `team_id` - The team.
+    /// * `employer` - The stream's employer.
+    /// * `worker` - The stream's worker.
+    /// * `token` - The stream's token.
+    /// * `rate` - The stream's per-second rate.
+    pub fn team_stream_opened(
+        e: Env,
+        team_id: u32,
+        employer: Address,
+        worker: Address,
+        token: Address,
+        rate: i128,
+    ) {
+        Self::require_stream_contract(&e);
+        let team = Self::load_team(&e, team_id);
+        if team.employer != employer {
+            panic!("Team belongs to another employer");
+        }
+        if !Self::is_team_member(e.clone(), team_id, worker) {
+            panic!("Worker not in team");
+        }
+        if team.budget_token != token {
+            panic!("Stream token does not match team budget");
+        }
+
+        let mut spend = Self::get_team_spend(e.clone(), team_id);
+        spend.committed_rate += rate;
+        spend.monthly_burn = spend.committed_rate * SECONDS_PER_MONTH;
+        if spend.monthly_burn > team.monthly_cap {
+            panic!("Team monthly budget exceeded");
+        }
+        spend.active_streams += 1;
+        e.storage().persistent().set(&DataKey::TeamSpend(team_id), &spend);
+    }
+
+    /// Records that a team-tagged stream closed, freeing its share of the budget.
+    /// Only the configured stream contract can call this.
+    pub fn team_stream_closed(e: Env, team_id: u32, rate: i128) {
+        Self::require_stream_contract(&e);
+        let mut spend = Self::get_team_spend(e.clone(), team_id);
+        spend.active_streams = spend.active_streams.saturating_sub(1);
+        spend.committed_rate = (spend.committed_rate - rate).max(0);
+        spend.monthly_burn = spend.committed_rate * SECONDS_PER_MONTH;
+        e.storage().persistent().set(&DataKey::TeamSpend(team_id), &spend);
+    }
+
+    /// Records a payout from a team-tagged stream.
+    /// Only the configured stream contract can call this.
+    pub fn team_stream_paid(e: Env, team_id: u32, amount: i128) {
+        Self::require_stream_contract(&e);
+        let mut spend = Self::get_team_spend(e.clone(), team_id);
+        spend.total_paid += amount;
+        e.storage().persistent().set(&DataKey::TeamSpend(team_id), &spend);
+    }
+
+    /// Aggregate spend of a team's tagged streams.
+    pub fn get_team_spend(e: Env, team_id: u32) -> TeamSpend {
+        e.storage()
+            .persistent()
+            .get(&DataKey::TeamSpend(team_id))
+            .unwrap_or_default()
+    }
+
+    /// Number of registered workers.
+    pub fn get_worker_count(e: Env) -> u32 {
+        Self::index_count(&e, WorkerIndex::All)
+    }
+
+    /// Paginates over every registered worker.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `start` - Index of the first worker to return.
+    /// * `limit` - Maximum number of workers to return.
+    ///
+    /// # Returns
+    /// * `Vec<WorkerProfile>` - The page of worker profiles.
+    pub fn get_workers(e: Env, start: u32, limit: u32) -> Vec<WorkerProfile> {
+        Self::profiles(&e, Self::index_page(&e, WorkerIndex::All, start, limit))
+    }
+
+    /// Number of registered workers preferring `token`.
+    pub fn get_worker_count_by_token(e: Env, token: Address) -> u32 {
+        Self::index_count(&e, WorkerIndex::Token(token))
+    }
+
+    /// Paginates over registered workers preferring `token`.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `token` - The preferred token.
+    /// * `start` - Index of the first worker to return.
+    /// * `limit` - Maximum number of workers to return.
+    ///
+    /// # Returns
+    /// * `Vec<WorkerProfile>` - The page of worker profiles.
+    pub fn get_workers_by_token(e: Env, token: Address, start: u32, limit: u32) -> Vec<WorkerProfile> {
+        Self::profiles(&e, Self::index_page(&e, WorkerIndex::Token(token), start, limit))
+    }
+
+    /// Number of distinct workers an employer has ever streamed to.
+    pub fn get_employer_history_count(e: Env, employer: Address) -> u32 {
+        Self::index_count(&e, WorkerIndex::EmployerHistory(employer))
+    }
+
+    /// Paginates over every worker an employer has ever streamed to, active or not.
+    /// Returns addresses because historical workers may since have deregistered.
+    ///
+    /// # Arguments
+    /// * `e` - The environment.
+    /// * `employer` - The employer.
+    /// * `start` - Index of the first worker to return.
+    /// * `limit` - Maximum number of workers to return.
+    ///
+    /// # Returns
+    /// * `Vec<Address>` - The page of worker addresses.
+    pub fn get_employer_worker_history(e: Env, employer: Address, start: u32, limit: u32) -> Vec<Address> {
+        Self::index_page(&e, WorkerIndex::EmployerHistory(employer), start, limit)
+    }
+
+    pub fn get_workers_by_employer(e: Env, employer: Address, start: u32, limit: u32) -> Vec<WorkerProfile> {
+        let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
+        let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
+
+        if start >= count || limit == 0 {
+            return Vec::new(&e);
+        }
+
+        let end_exclusive = if start.saturating_add(limit) > count {
+            count
+        } else {
+            start + limit
+        };
+
+        let mut out: Vec<WorkerProfile> = Vec::new(&e);
+        let mut i = start;
+        while i < end_exclusive {
+            let by_index_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), i);
+            let worker: Address = e.storage().persistent().get(&by_index_key).unwrap();
+            out.push_back(Self::load_worker(&e, &worker).unwrap());
+            i += 1;
+        }
+
+        out
+    }
+}
+
+impl WorkforceRegistryContract {
+    /// Loads a worker record, converting the legacy layout on the fly.
+    fn load_worker(e: &Env, worker: &Address) -> Option<WorkerProfile> {
+        let raw: Val = e
+            .storage()
+            .persistent()
+            .get(&DataKey::Worker(worker.clone()))?;
+        if !Self::is_legacy_record(e, raw) {
+            return Some(raw.try_into_val(e).unwrap());
+        }
+        let legacy: LegacyWorkerProfile = raw.try_into_val(e).unwrap();
+        Some(WorkerProfile {
+            wallet: legacy.wallet,
+            preferred_token: legacy.preferred_token,
+            metadata: metadata::from_legacy(e, &legacy.metadata_hash),
+        })
+    }
+
+    fn is_legacy(e: &Env, worker: &Address) -> bool {
+        let raw: Option<Val> = e.storage().persistent().get(&DataKey::Worker(worker.clone()));
+        match raw {
+            Some(raw) => Self::is_legacy_record(e, raw),
+            None => false,
+        }
+    }
+
+    fn is_legacy_record(e: &Env, raw: Val) -> bool {
+        let fields: Map<Symbol, Val> = raw.try_into_val(e).unwrap();
+        fields.contains_key(Symbol::new(e, "metadata_hash"))
+    }
+
+    fn stamp_metadata(e: &Env, mut metadata: WorkerMetadata) -> WorkerMetadata {
+        metadata::validate(e, &metadata);
+        metadata.updated_at = e.ledger().timestamp();
+        metadata
+    }
+
+    fn require_admin(e: &Env) -> Address {
+        let admin: Address = e
+            .storage()
+            .instance()
+            .get(&DataKey::Admin)
+            .expect("not initialized");
+        admin.require_auth();
+        admin
+    }
+
+    fn require_stream_contract(e: &Env) {
+        let stream_contract: Address = e
+            .storage()
+            .instance()
+            .get(&DataKey::StreamContract)
+            .expect("stream contract not set");
+        stream_contract.require_auth();
+    }
+
+    fn open_ref(e: &Env, employer: &Address, worker: &Address) {
+        let ref_key = DataKey::ActiveStreamCount(employer.clone(), worker.clone());
+        let live: u32 = e.storage().persistent().get(&ref_key).unwrap_or(0);
+        e.storage().persistent().set(&ref_key, &(live + 1));
+
+        let total_key = DataKey::WorkerLiveStreams(worker.clone());
+        let total: u32 = e.storage().persistent().get(&total_key).unwrap_or(0);
+        e.storage().persistent().set(&total_key, &(total + 1));
+
+        if live == 0 {
+            Self::activate(e, employer, worker);
+            Self::index_insert(e, WorkerIndex::EmployerHistory(employer.clone()), worker);
+        }
+    }
+
+    fn close_ref(e: &Env, employer: &Address, worker: &Address) {
+        let ref_key = DataKey::ActiveStreamCount(employer.clone(), worker.clone());
+        let live: u32 = e.storage().persistent().get(&ref_key).unwrap_or(0);
+        if live == 0 {
+            return;
+        }
+
+        let total_key = DataKey::WorkerLiveStreams(worker.clone());
+        let total: u32 = e.storage().persistent().get(&total_key).unwrap_or(0);
+        if total > 1 {
+            e.storage().persistent().set(&total_key, &(total - 1));
+        } else {
+            e.storage().persistent().remove(&total_key);
+        }
+
+        if live > 1 {
+            e.storage().persistent().set(&ref_key, &(live - 1));
+            return;
+        }
+
+        e.storage().persistent().remove(&ref_key);
+        Self::deactivate(e, employer, worker);
+    }
+
+    /// Moves a worker's profile, payout route, swap floors, guardian and live-stream tracking
+    /// to `new_wallet`, and has the stream contract repoint the worker's streams.
+    /// Workers with more streams than one page are finished by `resume_stream_migration`.
+    fn migrate_worker(e: &Env, worker: &Address, new_wallet: &Address) {
+        let old_key = DataKey::Worker(worker.clone());
+        let mut profile = Self::load_worker(e, worker).expect("Worker not registered");
+        let new_key = DataKey::Worker(new_wallet.clone());
+        if e.storage().persistent().has(&new_key) {
+            panic!("Worker already registered");
+        }
+
+        profile.wallet = new_wallet.clone();
+        e.storage().persistent().set(&new_key, &profile);
+        e.storage().persistent().remove(&old_key);
+        Self::index_remove(e, WorkerIndex::All, worker);
+        Self::index_insert(e, WorkerIndex::All, new_wallet);
+        Self::index_remove(e, WorkerIndex::Token(profile.preferred_token.clone()), worker);
+        Self::index_insert(e, WorkerIndex::Token(profile.preferred_token), new_wallet);
+        e.storage().persistent().remove(&DataKey::Recovery(worker.clone()));
+
+        let route_key = DataKey::PayoutRoute(worker.clone());
+        if let Some(route) = e.storage().persistent().get::<DataKey, Vec<PayoutShare>>(&route_key) {
+            e.storage()
+                .persistent()
+                .set(&DataKey::PayoutRoute(new_wallet.clone()), &route);
+            e.storage().persistent().remove(&route_key);
+        }
+
+        let floors_key = DataKey::SwapFloors(worker.clone());
+        if let Some(floors) = e.storage().persistent().get::<DataKey, Map<Address, i128>>(&floors_key) {
+            e.storage()
+                .persistent()
+                .set(&DataKey::SwapFloors(new_wallet.clone()), &floors);
+            e.storage().persistent().remove(&floors_key);
+        }
+
+        let guardian_key = DataKey::Guardian(worker.clone());
+        if let Some(guardian) = e.storage().persistent().get::<DataKey, Address>(&guardian_key) {
+            e.storage()
+                .persistent()
+                .set(&DataKey::Guardian(new_wallet.clone()), &guardian);
+            e.storage().persistent().remove(&guardian_key);
+        }
+
+        let types_key = DataKey::AttestationTypes(worker.clone());
+        let types: Vec<Symbol> = e
+            .storage()
+            .persistent()
+            .get(&types_key)
+            .unwrap_or_else(|| Vec::new(e));
+        for attestation_type in types.iter() {
+            let old = DataKey::Attestation(worker.clone(), attestation_type.clone());
+            if let Some(a) = e.storage().persistent().get::<DataKey, Attestation>(&old) {
+                e.storage()
+                    .persistent()
+                    .set(&DataKey::Attestation(new_wallet.clone(), attestation_type), &a);
+                e.storage().persistent().remove(&old);
+            }
+        }
+        if !types.is_empty() {
+            e.storage()
+                .persistent()
+                .set(&DataKey::AttestationTypes(new_wallet.clone()), &types);
+            e.storage().persistent().remove(&types_key);
+        }
+
+        let teams = Self::get_worker_teams(e.clone(), worker.clone());
+        for team_id in teams.iter() {
+            Self::index_remove(e, WorkerIndex::Team(team_id), worker);
+            Self::index_insert(e, WorkerIndex::Team(team_id), new_wallet);
+        }
+        if !teams.is_empty() {
+            e.storage()
+                .persistent()
+                .set(&DataKey::WorkerTeams(new_wallet.clone()), &teams);
+            e.storage()
+                .persistent()
+                .remove(&DataKey::WorkerTeams(worker.clone()));
+        }
+
+        Self::migrate_streams(e, worker, new_wallet, 0);
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("registry"),
+                symbol_short!("recovered"),
+                worker.clone(),
+                new_wallet.clone(),
+            ),
+            (),
+        );
+    }
+
+    /// Has the stream contract repoint one page of `worker`'s streams from slot
+    /// `cursor`. Remaining pages are recorded for `resume_stream_migration`.
+    /// Returns whether streams are still left to move.
+    fn migrate_streams(e: &Env, worker: &Address, new_wallet: &Address, cursor: u32) -> bool {
+        let pending_key = DataKey::StreamMigration(worker.clone());
+        let stream_contract: Option<Address> = e.storage().instance().get(&DataKey::StreamContract);
+        let Some(stream_contract) = stream_contract else {
+            e.storage().persistent().remove(&pending_key);
+            return false;
+        };
+
+        // The stream contract reports one employer per live stream it repointed
+        let (employers, next_cursor): (Vec<Address>, Option<u32>) = e.invoke_contract(
+            &stream_contract,
+            &Symbol::new(e, "migrate_worker"),
+            soroban_sdk::vec![
+                e,
+                worker.into_val(e),
+                new_wallet.into_val(e),
+                cursor.into_val(e),
+            ],
+        );
+        for employer in employers.iter() {
+            Self::close_ref(e, &employer, worker);
+            Self::open_ref(e, &employer, new_wallet);
+        }
+
+        match next_cursor {
+            Some(next) => {
+                e.storage()
+                    .persistent()
+                    .set(&pending_key, &(new_wallet.clone(), next));
+                true
+            }
+            None => {
+                e.storage().persistent().remove(&pending_key);
+                false
+            }
+        }
+    }
+
+    fn activate(e: &Env, employer: &Address, worker: &Address) {
+        let idx_key = DataKey::EmployerActiveWorkerIndex(employer.clone(), worker.clone());
+        if e.storage().persistent().has(&idx_key) {
+            return;
+        }
+
+        let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
+        let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
+
+        let by_index_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), count);
+        e.storage().persistent().set(&by_index_key, worker);
+
+        let stored_index: u32 = count + 1;
+        e.storage().persistent().set(&idx_key, &stored_index);
+        e.storage().persistent().set(&count_key, &(count + 1));
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("stream"),
+                symbol_short!("active"),
+                employer.clone(),
+                worker.clone(),
+            ),
+            (),
+        );
+    }
+
+    fn deactivate(e: &Env, employer: &Address, worker: &Address) {
+        let idx_key = DataKey::EmployerActiveWorkerIndex(employer.clone(), worker.clone());
+        if !e.storage().persistent().has(&idx_key) {
+            return;
+        }
+
+        let count_key = DataKey::EmployerActiveWorkerCount(employer.clone());
+        let count: u32 = e.storage().persistent().get(&count_key).unwrap_or(0);
+        if count == 0 {
+            e.storage().persistent().remove(&idx_key);
+            return;
+        }
+
+        let stored_index: u32 = e.storage().persistent().get(&idx_key).unwrap();
+        let remove_pos: u32 = stored_index - 1;
+        let last_pos: u32 = count - 1;
+
+        if remove_pos != last_pos {
+            let last_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), last_pos);
+            let last_worker: Address = e.storage().persistent().get(&last_key).unwrap();
+
+            let remove_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), remove_pos);
+            e.storage().persistent().set(&remove_key, &last_worker);
+
+            let last_worker_idx_key =
+                DataKey::EmployerActiveWorkerIndex(employer.clone(), last_worker.clone());
+            e.storage().persistent().set(&last_worker_idx_key, &(remove_pos + 1));
+
+            e.storage().persistent().remove(&last_key);
+        } else {
+            let last_key = DataKey::EmployerActiveWorkerByIndex(employer.clone(), last_pos);
+            e.storage().persistent().remove(&last_key);
+        }
+
+        e.storage().persistent().remove(&idx_key);
+        e.storage().persistent().set(&count_key, &(count - 1));
+
+        #[allow(deprecated)]
+        e.events().publish(
+            (
+                symbol_short!("stream"),
+                symbol_short!("inactive"),
+                employer.clone(),
+                worker.clone(),
+            ),
+            (),
+        );
+    }
+
+    fn load_employer(e: &Env, employer: &Address) -> EmployerProfile {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Employer(employer.clone()))
+            .unwrap_or_else(|| panic!("Employer not registered"))
+    }
+
+    fn is_admin(profile: &EmployerProfile, actor: &Address) -> bool {
+        *actor == profile.employer || profile.admins.contains(actor)
+    }
+
+    fn is_delegate(e: &Env, profile: &EmployerProfile, actor: &Address) -> bool {
+        Self::is_admin(profile, actor)
+            || e.storage().persistent().has(&DataKey::EmployerManager(
+                profile.employer.clone(),
+                actor.clone(),
+            ))
+    }
+
+    fn require_employer_admin(e: &Env, employer: &Address, caller: &Address) -> EmployerProfile {
+        let profile = Self::load_employer(e, employer);
+        if !Self::is_admin(&profile, caller) {
+            panic!("Not an employer admin");
+        }
+        profile
+    }
+
+    fn load_team(e: &Env, team_id: u32) -> Team {
+        e.storage()
+            .persistent()
+            .get(&DataKey::Team(team_id))
+            .unwrap_or_else(|| panic!("Team not found"))
+    }
+
+    /// Employer admins, sub-managers and the team's own manager may manage members.
+    fn require_team_manager(e: &Env, team: &Team, caller: &Address) {
+        let profile = Self::load_employer(e, &team.employer);
+        if *caller != team.manager && !Self::is_delegate(e, &profile, caller) {
+            panic!("Not a team manager");
+        }
+    }
+
+    fn leave_team(e: &Env, team_id: u32, worker: &Address) {
+        Self::index_remove(e, WorkerIndex::Team(team_id), worker);
+        let teams_key = DataKey::WorkerTeams(worker.clone());
+        let mut teams: Vec<u32> = e
+            .storage()
+            .persistent()
+            .get(&teams_key)
+            .unwrap_or_else(|| Vec::new(e));
+        if let Some(idx) = teams.first_index_of(team_id) {
+            teams.remove(idx);
+        }
+        if teams.is_empty() {
+            e.storage().persistent().remove(&teams_key);
+        } else {
+            e.storage().persistent().set(&teams_key, &teams);
+        }
+    }
+
+    fn index_count(e: &Env, index: WorkerIndex) -> u32 {
+        e.storage()
+            .persistent()
+            .get(&DataKey::IndexCount(index))
+            .unwrap_or(0)
+    }
+
+    fn index_insert(e: &Env, index: WorkerIndex, worker: &Address) {
+        let pos_key = DataKey::IndexPosition(index.clone(), worker.clone());
+        if e.storage().persistent().has(&pos_key) {
+            return;
+        }
+        let count = Self::index_count(e, index.clone());
+        e.storage()
+            .persistent()
+            .set(&DataKey::IndexEntry(index.clone(), count), worker);
+        e.storage().persistent().set(&pos_key, &count);
+        e.storage()
+            .persistent()
+            .set(&DataKey::IndexCount(index), &(count + 1));
+    }
+
+    /// Swap-removes `worker`, moving the last entry into its slot.
+    fn index_remove(e: &Env, index: WorkerIndex, worker: &Address) {
+        let pos_key = DataKey::IndexPosition(index.clone(), worker.clone());
+        let pos: u32 = match e.storage().persistent().get(&pos_key) {
+            Some(pos) => pos,
+            None => return,
+        };
+        let last = Self::index_count(e, index.clone()) - 1;
+        if pos != last {
+            let moved: Address = e
+                .storage()
+                .persistent()
+                .get(&DataKey::IndexEntry(index.clone(), last))
+                .unwrap();
+            e.storage()
+                .persistent()
+                .set(&DataKey::IndexEntry(index.clone(), pos), &moved);
+            e.storage()
+                .persistent()
+                .set(&DataKey::IndexPosition(index.clone(), moved), &pos);
+        }
+        e.storage()
+            .persistent()
+            .remove(&DataKey::IndexEntry(index.clone(), last));
+        e.storage().persistent().remove(&pos_key);
+        e.storage()
+            .persistent()
+            .set(&DataKey::IndexCount(index), &last);
+    }
+
+    fn index_page(e: &Env, index: WorkerIndex, start: u32, limit: u32) -> Vec<Address> {
+        let count = Self::index_count(e, index.clone());
+        let mut out: Vec<Address> = Vec::new(e);
+        let end = start.saturating_add(limit).min(count);
+        let mut i = start;
+        while i < end {
+            let worker: Address = e
+                .storage()
+                .persistent()
+                .get(&DataKey::IndexEntry(index.clone(), i))
+                .unwrap();
+            out.push_back(worker);
+            i += 1;
+        }
+        out
+    }
+
+    fn profiles(e: &Env, workers: Vec<Address>) -> Vec<WorkerProfile> {
+        let mut out: Vec<WorkerProfile> = Vec::new(e);
+        for worker in workers.iter() {
+            out.push_back(Self::load_worker(e, &worker).unwrap());
+        }
         out
     }
 }
@@ -0,0 +1,101 @@
+use quipay_common::QuipayError;
+use soroban_sdk::{contracttype, panic_with_error, Address, Bytes, BytesN, Env, String};
+
+/// Longest accepted metadata locator, in bytes.
+pub const MAX_URI_LEN: u32 = 128;
+/// Schema version assigned to records converted from the untyped `metadata_hash`.
+pub const LEGACY_SCHEMA_VERSION: u32 = 0;
+
+/// Where the off-chain metadata document is stored.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[repr(u32)]
+pub enum StorageScheme {
+    Ipfs = 0,
+    Arweave = 1,
+    Https = 2,
+}
+
+/// Pointer to a worker's off-chain metadata document.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorkerMetadata {
+    /// SHA-256 of the document, so its contents can be verified after fetching.
+    pub content_hash: BytesN<32>,
+    pub scheme: StorageScheme,
+    /// `ipfs://<cid>`, `ar://<tx id>` or an `https://` URL, matching `scheme`.
+    pub uri: String,
+    /// Version of the off-chain document schema; must be at least 1.
+    pub schema_version: u32,
+    /// Set by the registry whenever the metadata is written.
+    pub updated_at: u64,
+}
+
+/// Worker record layout used before metadata was typed. Still readable so
+/// existing registrations keep working until `migrate_workers` rewrites them.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct LegacyWorkerProfile {
+    pub wallet: Address,
+    pub preferred_token: Address,
+    pub metadata_hash: String,
+}
+
+/// Panics with a `QuipayError` if `metadata` is malformed.
+pub fn validate(e: &Env, metadata: &WorkerMetadata) {
+    if metadata.uri.is_empty() {
+        panic_with_error!(e, QuipayError::EmptyMetadataUri);
+    }
+    if metadata.uri.len() > MAX_URI_LEN {
+        panic_with_error!(e, QuipayError::MetadataUriTooLong);
+    }
+    if metadata.schema_version == LEGACY_SCHEMA_VERSION {
+        panic_with_error!(e, QuipayError::InvalidSchemaVersion);
+    }
+    let prefix = scheme_prefix(metadata.scheme);
+    if !has_prefix(e, &metadata.uri, prefix) {
+        panic_with_error!(e, QuipayError::MetadataSchemeMismatch);
+    }
+    if metadata.uri.len() == prefix.len() as u32 {
+        panic_with_error!(e, QuipayError::EmptyMetadataUri);
+    }
+}
+
+/// Locator prefix every URI stored under `scheme` must carry.
+fn scheme_prefix(scheme: StorageScheme) -> &'static [u8] {
+    match scheme {
+        StorageScheme::Ipfs => b"ipfs://",
+        StorageScheme::Arweave => b"ar://",
+        StorageScheme::Https => b"https://",
+    }
+}
+
+/// Best-effort conversion of an untyped `metadata_hash`. The content hash is
+/// derived from the string itself, and oversized strings keep only their hash.
+pub fn from_legacy(e: &Env, metadata_hash: &String) -> WorkerMetadata {
+    let scheme = if has_prefix(e, metadata_hash, b"https://") {
+        StorageScheme::Https
+    } else if has_prefix(e, metadata_hash, b"ar://") {
+        StorageScheme::Arweave
+    } else {
+        StorageScheme::Ipfs
+    };
+    let uri = if metadata_hash.len() <= MAX_URI_LEN {
+        metadata_hash.clone()
+    } else {
+        String::from_str(e, "")
+    };
+
+    WorkerMetadata {
+        content_hash: e.crypto().sha256(&metadata_hash.to_bytes()).into(),
+        scheme,
+        uri,
+        schema_version: LEGACY_SCHEMA_VERSION,
+        updated_at: 0,
+    }
+}
+
+fn has_prefix(e: &Env, s: &String, prefix: &[u8]) -> bool {
+    let len = prefix.len() as u32;
+    s.len() >= len && s.to_bytes().slice(0..len) == Bytes::from_slice(e, prefix)
+}
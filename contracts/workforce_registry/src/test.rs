@@ -3,9 +3,37 @@
 extern crate std;
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
+use quipay_common::QuipayError;
+use soroban_sdk::{testutils::{Address as _, Ledger as _}, Address, BytesN, Env, String};
 use std::vec::Vec as StdVec;
 
+fn metadata(e: &Env, cid: &str) -> WorkerMetadata {
+    WorkerMetadata {
+        content_hash: BytesN::from_array(e, &[4u8; 32]),
+        scheme: StorageScheme::Ipfs,
+        uri: String::from_str(e, cid),
+        schema_version: 1,
+        updated_at: 0,
+    }
+}
+
+fn register_employer(e: &Env, client: &WorkforceRegistryContractClient, employer: &Address) {
+    client.register_employer(
+        employer,
+        &BytesN::from_array(e, &[7u8; 32]),
+        &String::from_str(e, "QmEmployer"),
+        &Address::generate(e),
+        &soroban_sdk::Vec::new(e),
+    );
+}
+
+fn link_stream_contract(e: &Env, client: &WorkforceRegistryContractClient) -> Address {
+    let stream_contract = Address::generate(e);
+    client.init(&Address::generate(e));
+    client.set_stream_contract(&stream_contract);
+    stream_contract
+}
+
 #[test]
 fn test_register_and_get_worker() {
     let e = Env::default();
@@ -15,7 +43,7 @@ fn test_register_and_get_worker() {
 
     let worker = Address::generate(&e);
     let preferred_token = Address::generate(&e);
-    let metadata_hash = String::from_str(&e, "QmHash123");
+    let metadata_hash = metadata(&e, "ipfs://QmHash123");
 
     // Test initial state
     assert_eq!(client.is_registered(&worker), false);
@@ -30,7 +58,8 @@ fn test_register_and_get_worker() {
     let profile = client.get_worker(&worker).unwrap();
     assert_eq!(profile.wallet, worker);
     assert_eq!(profile.preferred_token, preferred_token);
-    assert_eq!(profile.metadata_hash, metadata_hash);
+    assert_eq!(profile.metadata.uri, metadata_hash.uri);
+    assert_eq!(profile.metadata.content_hash, metadata_hash.content_hash);
 }
 
 #[test]
@@ -43,8 +72,8 @@ fn test_update_worker() {
     let worker = Address::generate(&e);
     let token1 = Address::generate(&e);
     let token2 = Address::generate(&e);
-    let hash1 = String::from_str(&e, "QmHash1");
-    let hash2 = String::from_str(&e, "QmHash2");
+    let hash1 = metadata(&e, "ipfs://QmHash1");
+    let hash2 = metadata(&e, "ipfs://QmHash2");
 
     client.register_worker(&worker, &token1, &hash1);
     
@@ -53,7 +82,7 @@ fn test_update_worker() {
 
     let profile = client.get_worker(&worker).unwrap();
     assert_eq!(profile.preferred_token, token2);
-    assert_eq!(profile.metadata_hash, hash2);
+    assert_eq!(profile.metadata.uri, hash2.uri);
 }
 
 #[test]
@@ -66,7 +95,7 @@ fn test_duplicate_registration() {
 
     let worker = Address::generate(&e);
     let token = Address::generate(&e);
-    let hash = String::from_str(&e, "QmHash");
+    let hash = metadata(&e, "ipfs://QmHash");
 
     client.register_worker(&worker, &token, &hash);
     client.register_worker(&worker, &token, &hash);
@@ -82,7 +111,7 @@ fn test_update_nonexistent_worker() {
 
     let worker = Address::generate(&e);
     let token = Address::generate(&e);
-    let hash = String::from_str(&e, "QmHash");
+    let hash = metadata(&e, "ipfs://QmHash");
 
     client.update_worker(&worker, &token, &hash);
 }
@@ -96,14 +125,16 @@ fn test_get_workers_by_employer_pagination() {
 
     let employer = Address::generate(&e);
     let preferred_token = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
 
     let mut workers: StdVec<Address> = StdVec::new();
     let mut i: u32 = 0;
     while i < 10 {
         let worker = Address::generate(&e);
-        let metadata_hash = String::from_str(&e, "QmHash");
+        let metadata_hash = metadata(&e, "ipfs://QmHash");
         client.register_worker(&worker, &preferred_token, &metadata_hash);
-        client.set_stream_active(&employer, &worker, &true);
+        client.stream_opened(&employer, &worker);
         workers.push(worker);
         i += 1;
     }
@@ -137,24 +168,26 @@ fn test_get_workers_by_employer_only_active_streams() {
 
     let employer = Address::generate(&e);
     let preferred_token = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
 
     let w1 = Address::generate(&e);
     let w2 = Address::generate(&e);
     let w3 = Address::generate(&e);
-    let metadata_hash = String::from_str(&e, "QmHash");
+    let metadata_hash = metadata(&e, "ipfs://QmHash");
 
     client.register_worker(&w1, &preferred_token, &metadata_hash);
     client.register_worker(&w2, &preferred_token, &metadata_hash);
     client.register_worker(&w3, &preferred_token, &metadata_hash);
 
-    client.set_stream_active(&employer, &w1, &true);
-    client.set_stream_active(&employer, &w2, &true);
-    client.set_stream_active(&employer, &w3, &true);
+    client.stream_opened(&employer, &w1);
+    client.stream_opened(&employer, &w2);
+    client.stream_opened(&employer, &w3);
 
     let all = client.get_workers_by_employer(&employer, &0u32, &10u32);
     assert_eq!(all.len(), 3);
 
-    client.set_stream_active(&employer, &w2, &false);
+    client.stream_closed(&employer, &w2);
 
     let after = client.get_workers_by_employer(&employer, &0u32, &10u32);
     assert_eq!(after.len(), 2);
@@ -172,13 +205,15 @@ fn test_query_performance_scales_with_page_size() {
 
     let employer = Address::generate(&e);
     let preferred_token = Address::generate(&e);
-    let metadata_hash = String::from_str(&e, "QmHash");
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
+    let metadata_hash = metadata(&e, "ipfs://QmHash");
 
     let mut i: u32 = 0;
     while i < 200 {
         let worker = Address::generate(&e);
         client.register_worker(&worker, &preferred_token, &metadata_hash);
-        client.set_stream_active(&employer, &worker, &true);
+        client.stream_opened(&employer, &worker);
         i += 1;
     }
 
@@ -199,3 +234,745 @@ fn test_query_performance_scales_with_page_size() {
     assert!(large_cost > small_cost);
     assert!(large_cost < small_cost.saturating_mul(20));
 }
+
+#[test]
+fn test_register_and_update_employer() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let admin = Address::generate(&e);
+    let token = Address::generate(&e);
+    let name_hash = BytesN::from_array(&e, &[1u8; 32]);
+    let metadata = String::from_str(&e, "QmAcme");
+
+    assert!(!client.is_employer_registered(&employer));
+    assert_eq!(client.get_employer(&employer), None);
+
+    client.register_employer(
+        &employer,
+        &name_hash,
+        &metadata,
+        &token,
+        &soroban_sdk::vec![&e, admin.clone()],
+    );
+    assert!(client.is_employer_registered(&employer));
+
+    let profile = client.get_employer(&employer).unwrap();
+    assert_eq!(profile.employer, employer);
+    assert_eq!(profile.name_hash, name_hash);
+    assert_eq!(profile.default_token, token);
+    assert_eq!(profile.admins.len(), 1);
+
+    // Admins may update the profile on the employer's behalf
+    let new_token = Address::generate(&e);
+    let new_hash = BytesN::from_array(&e, &[2u8; 32]);
+    client.update_employer(
+        &admin,
+        &employer,
+        &new_hash,
+        &metadata,
+        &new_token,
+        &soroban_sdk::Vec::new(&e),
+    );
+    let profile = client.get_employer(&employer).unwrap();
+    assert_eq!(profile.default_token, new_token);
+    assert_eq!(profile.name_hash, new_hash);
+    assert_eq!(profile.admins.len(), 0);
+}
+
+#[test]
+#[should_panic(expected = "Employer already registered")]
+fn test_duplicate_employer_registration() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    register_employer(&e, &client, &employer);
+}
+
+#[test]
+#[should_panic(expected = "Employer not registered")]
+fn test_stream_opened_requires_registered_employer() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+    link_stream_contract(&e, &client);
+
+    let employer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    client.stream_opened(&employer, &worker);
+}
+
+#[test]
+fn test_manager_can_act_for_employer() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let manager = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+
+    assert!(client.can_act_for(&employer, &employer));
+    assert!(!client.can_act_for(&employer, &manager));
+
+    client.add_manager(&employer, &employer, &manager);
+    assert!(client.can_act_for(&employer, &manager));
+
+    client.remove_manager(&employer, &employer, &manager);
+    assert!(!client.can_act_for(&employer, &manager));
+}
+
+#[test]
+#[should_panic(expected = "Not an employer admin")]
+fn test_manager_cannot_add_managers() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let manager = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    client.add_manager(&employer, &employer, &manager);
+    client.add_manager(&manager, &employer, &Address::generate(&e));
+}
+
+#[test]
+fn test_active_worker_ref_counts_live_streams() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    client.stream_opened(&employer, &worker);
+    client.stream_opened(&employer, &worker);
+    assert_eq!(client.get_active_stream_count(&employer, &worker), 2);
+    assert_eq!(client.get_workers_by_employer(&employer, &0u32, &10u32).len(), 1);
+
+    // Closing one of two streams keeps the worker active
+    client.stream_closed(&employer, &worker);
+    assert_eq!(client.get_active_stream_count(&employer, &worker), 1);
+    assert_eq!(client.get_workers_by_employer(&employer, &0u32, &10u32).len(), 1);
+
+    client.stream_closed(&employer, &worker);
+    assert_eq!(client.get_active_stream_count(&employer, &worker), 0);
+    assert_eq!(client.get_workers_by_employer(&employer, &0u32, &10u32).len(), 0);
+
+    // Extra close notifications are ignored
+    client.stream_closed(&employer, &worker);
+    assert_eq!(client.get_active_stream_count(&employer, &worker), 0);
+}
+
+#[test]
+fn test_only_stream_contract_updates_active_workers() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    // No stream contract configured yet
+    assert!(client.try_stream_opened(&employer, &worker).is_err());
+
+    let stream_contract = link_stream_contract(&e, &client);
+    assert_eq!(client.get_stream_contract(), Some(stream_contract));
+
+    // Without the stream contract's authorization the call is rejected
+    e.set_auths(&[]);
+    assert!(client.try_stream_opened(&employer, &worker).is_err());
+    assert_eq!(client.get_active_stream_count(&employer, &worker), 0);
+}
+
+fn share(recipient: &Address, bps: u32) -> PayoutShare {
+    PayoutShare {
+        recipient: recipient.clone(),
+        bps,
+    }
+}
+
+#[test]
+fn test_set_and_clear_payout_route() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let spending = Address::generate(&e);
+    let savings = Address::generate(&e);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    assert_eq!(client.get_payout_route(&worker).len(), 0);
+
+    let shares = soroban_sdk::vec![&e, share(&spending, 7_000), share(&savings, 3_000)];
+    client.set_payout_route(&worker, &shares);
+    assert_eq!(client.get_payout_route(&worker), shares);
+
+    client.clear_payout_route(&worker);
+    assert_eq!(client.get_payout_route(&worker).len(), 0);
+}
+
+#[test]
+fn test_swap_floor_is_per_token_and_reset_with_preferred_token() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let usdc = Address::generate(&e);
+    let xlm = Address::generate(&e);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    assert_eq!(client.get_swap_floor(&worker, &usdc), None);
+
+    client.set_swap_floor(&worker, &usdc, &19_500);
+    client.set_swap_floor(&worker, &xlm, &1_200);
+    assert_eq!(client.get_swap_floor(&worker, &usdc), Some(19_500));
+
+    client.set_swap_floor(&worker, &xlm, &0);
+    assert_eq!(client.get_swap_floor(&worker, &xlm), None);
+    assert!(client.try_set_swap_floor(&worker, &xlm, &-1).is_err());
+
+    client.update_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    assert_eq!(client.get_swap_floor(&worker, &usdc), None);
+}
+
+#[test]
+#[should_panic(expected = "Payout shares must sum to 10000")]
+fn test_payout_route_must_sum_to_full() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    client.set_payout_route(
+        &worker,
+        &soroban_sdk::vec![
+            &e,
+            share(&Address::generate(&e), 7_000),
+            share(&Address::generate(&e), 2_000)
+        ],
+    );
+}
+
+#[test]
+fn test_payout_route_rejects_invalid_shares() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let dest = Address::generate(&e);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    let empty = soroban_sdk::Vec::new(&e);
+    assert!(client.try_set_payout_route(&worker, &empty).is_err());
+
+    let duplicate = soroban_sdk::vec![&e, share(&dest, 5_000), share(&dest, 5_000)];
+    assert!(client.try_set_payout_route(&worker, &duplicate).is_err());
+
+    let zero = soroban_sdk::vec![&e, share(&dest, 10_000), share(&worker, 0)];
+    assert!(client.try_set_payout_route(&worker, &zero).is_err());
+
+    let stranger = Address::generate(&e);
+    let full = soroban_sdk::vec![&e, share(&dest, 10_000)];
+    assert!(client.try_set_payout_route(&stranger, &full).is_err());
+}
+
+#[test]
+fn test_deregister_worker() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let token = Address::generate(&e);
+    let hash = metadata(&e, "ipfs://QmHash");
+    client.register_worker(&worker, &token, &hash);
+    client.set_payout_route(&worker, &soroban_sdk::vec![&e, share(&worker, 10_000)]);
+
+    client.deregister_worker(&worker);
+    assert!(!client.is_registered(&worker));
+    assert_eq!(client.get_payout_route(&worker).len(), 0);
+
+    // The address can register again from scratch
+    client.register_worker(&worker, &token, &hash);
+    assert!(client.is_registered(&worker));
+}
+
+#[test]
+fn test_deregister_blocked_while_streams_active() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    client.stream_opened(&employer, &worker);
+    assert_eq!(client.get_live_stream_count(&worker), 1);
+    assert!(client.try_deregister_worker(&worker).is_err());
+
+    client.stream_closed(&employer, &worker);
+    assert_eq!(client.get_live_stream_count(&worker), 0);
+    client.deregister_worker(&worker);
+    assert!(!client.is_registered(&worker));
+}
+
+#[test]
+fn test_guardian_recovers_profile() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let guardian = Address::generate(&e);
+    let new_wallet = Address::generate(&e);
+    let token = Address::generate(&e);
+    let savings = Address::generate(&e);
+    let issuer = Address::generate(&e);
+    let kyc = Symbol::new(&e, "KYC");
+    client.register_worker(&worker, &token, &metadata(&e, "ipfs://QmHash"));
+    client.set_payout_route(&worker, &soroban_sdk::vec![&e, share(&savings, 10_000)]);
+    client.init(&Address::generate(&e));
+    client.add_issuer(&issuer);
+    client.attest(&issuer, &worker, &kyc, &BytesN::from_array(&e, &[9u8; 32]), &1_000);
+
+    assert!(client.try_recover_worker(&worker, &new_wallet).is_err());
+
+    client.set_guardian(&worker, &guardian);
+    client.recover_worker(&worker, &new_wallet);
+
+    assert!(!client.is_registered(&worker));
+    let profile = client.get_worker(&new_wallet).unwrap();
+    assert_eq!(profile.wallet, new_wallet);
+    assert_eq!(profile.preferred_token, token);
+    assert_eq!(client.get_payout_route(&new_wallet).len(), 1);
+    assert_eq!(client.get_guardian(&new_wallet), Some(guardian));
+    assert_eq!(client.get_guardian(&worker), None);
+    assert!(client.has_attestations(&new_wallet, &soroban_sdk::vec![&e, kyc.clone()]));
+    assert_eq!(client.get_attestation(&worker, &kyc), None);
+}
+
+#[test]
+fn test_employer_recovery_timelock_and_cancel() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let stranger = Address::generate(&e);
+    let worker = Address::generate(&e);
+    let new_wallet = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    register_employer(&e, &client, &stranger);
+    link_stream_contract(&e, &client);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+    client.stream_opened(&employer, &worker);
+
+    // Only employers currently paying the worker may start a recovery
+    assert!(client.try_initiate_recovery(&stranger, &worker, &new_wallet).is_err());
+
+    e.ledger().with_mut(|li| { li.timestamp = 1_000; });
+    client.initiate_recovery(&employer, &worker, &new_wallet);
+    let request = client.get_recovery(&worker).unwrap();
+    assert_eq!(request.new_wallet, new_wallet);
+    assert_eq!(request.executable_at, 1_000 + 7 * 24 * 60 * 60);
+    assert!(client.try_execute_recovery(&worker).is_err());
+
+    // The worker still holds their key and vetoes the recovery
+    assert!(client.try_cancel_recovery(&stranger, &worker).is_err());
+    client.cancel_recovery(&worker, &worker);
+    assert_eq!(client.get_recovery(&worker), None);
+    assert!(client.is_registered(&worker));
+}
+
+#[test]
+fn test_attestation_lifecycle() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let admin = Address::generate(&e);
+    let issuer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    let kyc = Symbol::new(&e, "KYC");
+    let cert = Symbol::new(&e, "CERT");
+    let hash = BytesN::from_array(&e, &[3u8; 32]);
+    client.init(&admin);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    assert!(client.try_attest(&issuer, &worker, &kyc, &hash, &1_000).is_err());
+    client.add_issuer(&issuer);
+    assert!(client.is_issuer(&issuer));
+
+    e.ledger().with_mut(|li| { li.timestamp = 100; });
+    client.attest(&issuer, &worker, &kyc, &hash, &1_000);
+    let attestation = client.get_attestation(&worker, &kyc).unwrap();
+    assert_eq!(attestation.issuer, issuer);
+    assert_eq!(attestation.hash, hash);
+    assert_eq!(attestation.issued_at, 100);
+
+    assert!(client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc.clone()]));
+    assert!(!client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc.clone(), cert.clone()]));
+    assert!(client.has_attestations(&worker, &soroban_sdk::Vec::new(&e)));
+
+    // Expired attestations no longer count
+    e.ledger().with_mut(|li| { li.timestamp = 1_000; });
+    assert!(!client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc.clone()]));
+    client.attest(&issuer, &worker, &kyc, &hash, &2_000);
+    assert!(client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc.clone()]));
+
+    // Removing the issuer invalidates what it attested
+    client.remove_issuer(&issuer);
+    assert!(!client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc.clone()]));
+    client.add_issuer(&issuer);
+
+    assert!(client
+        .try_revoke_attestation(&Address::generate(&e), &worker, &kyc)
+        .is_err());
+    client.revoke_attestation(&issuer, &worker, &kyc);
+    assert_eq!(client.get_attestation(&worker, &kyc), None);
+    assert!(!client.has_attestations(&worker, &soroban_sdk::vec![&e, kyc]));
+}
+
+#[test]
+#[should_panic(expected = "Attestation already expired")]
+fn test_attest_rejects_past_expiry() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let issuer = Address::generate(&e);
+    let worker = Address::generate(&e);
+    client.init(&Address::generate(&e));
+    client.add_issuer(&issuer);
+    client.register_worker(&worker, &Address::generate(&e), &metadata(&e, "ipfs://QmHash"));
+
+    e.ledger().with_mut(|li| { li.timestamp = 500; });
+    client.attest(
+        &issuer,
+        &worker,
+        &Symbol::new(&e, "KYC"),
+        &BytesN::from_array(&e, &[3u8; 32]),
+        &500,
+    );
+}
+
+#[test]
+fn test_global_and_token_worker_indexes() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let usdc = Address::generate(&e);
+    let eurc = Address::generate(&e);
+    let hash = metadata(&e, "ipfs://QmHash");
+    let mut workers: StdVec<Address> = StdVec::new();
+    for i in 0..5 {
+        let worker = Address::generate(&e);
+        let token = if i < 3 { &usdc } else { &eurc };
+        client.register_worker(&worker, token, &hash);
+        workers.push(worker);
+    }
+
+    assert_eq!(client.get_worker_count(), 5);
+    assert_eq!(client.get_worker_count_by_token(&usdc), 3);
+    assert_eq!(client.get_worker_count_by_token(&eurc), 2);
+    assert_eq!(client.get_workers(&0u32, &3u32).len(), 3);
+    assert_eq!(client.get_workers(&3u32, &10u32).len(), 2);
+    assert_eq!(client.get_workers(&5u32, &10u32).len(), 0);
+
+    // Switching preferred token moves the worker between token indexes
+    client.update_worker(&workers[0], &eurc, &hash);
+    assert_eq!(client.get_worker_count_by_token(&usdc), 2);
+    assert_eq!(client.get_worker_count_by_token(&eurc), 3);
+    assert!(client
+        .get_workers_by_token(&eurc, &0u32, &10u32)
+        .iter()
+        .any(|p| p.wallet == workers[0]));
+
+    // Deregistration swap-removes from every index
+    client.deregister_worker(&workers[1]);
+    assert_eq!(client.get_worker_count(), 4);
+    assert_eq!(client.get_worker_count_by_token(&usdc), 1);
+
+    let all = client.get_workers(&0u32, &10u32);
+    assert_eq!(all.len(), 4);
+    for (i, worker) in workers.iter().enumerate() {
+        assert_eq!(all.iter().any(|p| p.wallet == *worker), i != 1);
+    }
+}
+
+#[test]
+fn test_employer_history_keeps_inactive_workers() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+    link_stream_contract(&e, &client);
+
+    let w1 = Address::generate(&e);
+    let w2 = Address::generate(&e);
+    let hash = metadata(&e, "ipfs://QmHash");
+    client.register_worker(&w1, &Address::generate(&e), &hash);
+    client.register_worker(&w2, &Address::generate(&e), &hash);
+
+    client.stream_opened(&employer, &w1);
+    client.stream_opened(&employer, &w2);
+    client.stream_closed(&employer, &w1);
+    client.stream_opened(&employer, &w1);
+    client.stream_closed(&employer, &w1);
+
+    assert_eq!(client.get_workers_by_employer(&employer, &0u32, &10u32).len(), 1);
+    assert_eq!(client.get_employer_history_count(&employer), 2);
+    let history = client.get_employer_worker_history(&employer, &0u32, &10u32);
+    assert_eq!(history, soroban_sdk::vec![&e, w1.clone(), w2.clone()]);
+
+    // History survives deregistration
+    client.deregister_worker(&w1);
+    assert_eq!(client.get_employer_worker_history(&employer, &0u32, &1u32), soroban_sdk::vec![&e, w1]);
+}
+
+#[test]
+fn test_metadata_validation() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+    let worker = Address::generate(&e);
+    let token = Address::generate(&e);
+
+    let expect_err = |m: WorkerMetadata, err: QuipayError| {
+        assert_eq!(
+            client.try_register_worker(&worker, &token, &m),
+            Err(Ok(err.into()))
+        );
+    };
+
+    expect_err(metadata(&e, ""), QuipayError::EmptyMetadataUri);
+    expect_err(metadata(&e, "ipfs://"), QuipayError::EmptyMetadataUri);
+
+    let long = std::format!("ipfs://{}", "a".repeat(122));
+    expect_err(metadata(&e, &long), QuipayError::MetadataUriTooLong);
+
+    let mut unversioned = metadata(&e, "ipfs://QmHash");
+    unversioned.schema_version = 0;
+    expect_err(unversioned, QuipayError::InvalidSchemaVersion);
+
+    // Every scheme requires its own locator prefix
+    expect_err(metadata(&e, "QmHash"), QuipayError::MetadataSchemeMismatch);
+    expect_err(metadata(&e, "https://example.com/w.json"), QuipayError::MetadataSchemeMismatch);
+
+    let mut https_without_url = metadata(&e, "ipfs://QmHash");
+    https_without_url.scheme = StorageScheme::Https;
+    expect_err(https_without_url, QuipayError::MetadataSchemeMismatch);
+
+    let mut arweave_without_prefix = metadata(&e, "bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U");
+    arweave_without_prefix.scheme = StorageScheme::Arweave;
+    expect_err(arweave_without_prefix, QuipayError::MetadataSchemeMismatch);
+
+    let mut arweave = metadata(&e, "ar://bNbA3TEQVL60xlgCcqdz4ZPHFZ711cZ3hmkpGttDt_U");
+    arweave.scheme = StorageScheme::Arweave;
+    assert!(client.try_register_worker(&worker, &token, &arweave).is_ok());
+    client.deregister_worker(&worker);
+
+    let mut https = metadata(&e, "https://example.com/w.json");
+    https.scheme = StorageScheme::Https;
+    client.register_worker(&worker, &token, &https);
+    assert_eq!(client.get_worker(&worker).unwrap().metadata.scheme, StorageScheme::Https);
+}
+
+#[test]
+fn test_metadata_updated_at_is_stamped() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+    let worker = Address::generate(&e);
+    let token = Address::generate(&e);
+
+    e.ledger().with_mut(|li| { li.timestamp = 500; });
+    let mut m = metadata(&e, "ipfs://QmHash");
+    m.updated_at = 12_345;
+    client.register_worker(&worker, &token, &m);
+    assert_eq!(client.get_worker(&worker).unwrap().metadata.updated_at, 500);
+
+    e.ledger().with_mut(|li| { li.timestamp = 900; });
+    client.update_worker(&worker, &token, &metadata(&e, "ipfs://QmHash2"));
+    assert_eq!(client.get_worker(&worker).unwrap().metadata.updated_at, 900);
+}
+
+#[test]
+fn test_legacy_worker_records_migrate() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let token = Address::generate(&e);
+    let legacy_hash = String::from_str(&e, "ar://legacy-tx");
+    e.as_contract(&contract_id, || {
+        e.storage().persistent().set(
+            &DataKey::Worker(worker.clone()),
+            &LegacyWorkerProfile {
+                wallet: worker.clone(),
+                preferred_token: token.clone(),
+                metadata_hash: legacy_hash.clone(),
+            },
+        );
+    });
+
+    // Legacy records stay readable before migration
+    assert!(client.needs_migration(&worker));
+    let profile = client.get_worker(&worker).unwrap();
+    assert_eq!(profile.preferred_token, token);
+    assert_eq!(profile.metadata.scheme, StorageScheme::Arweave);
+    assert_eq!(profile.metadata.uri, legacy_hash);
+    assert_eq!(profile.metadata.schema_version, 0);
+    let expected_hash: BytesN<32> = e.crypto().sha256(&legacy_hash.to_bytes()).into();
+    assert_eq!(profile.metadata.content_hash, expected_hash);
+    assert_eq!(client.get_worker_count(), 0);
+
+    let unknown = Address::generate(&e);
+    assert_eq!(client.migrate_workers(&soroban_sdk::vec![&e, worker.clone(), unknown]), 1);
+    assert!(!client.needs_migration(&worker));
+    assert_eq!(client.get_worker(&worker), Some(profile));
+    assert_eq!(client.get_worker_count(), 1);
+    assert_eq!(client.get_worker_count_by_token(&token), 1);
+    assert_eq!(client.migrate_workers(&soroban_sdk::vec![&e, worker.clone()]), 0);
+
+    // The worker then supplies real metadata
+    client.update_worker(&worker, &token, &metadata(&e, "ipfs://QmFresh"));
+    assert_eq!(client.get_worker(&worker).unwrap().metadata.schema_version, 1);
+}
+
+#[test]
+fn test_updating_legacy_worker_indexes_it() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let worker = Address::generate(&e);
+    let token = Address::generate(&e);
+    e.as_contract(&contract_id, || {
+        e.storage().persistent().set(
+            &DataKey::Worker(worker.clone()),
+            &LegacyWorkerProfile {
+                wallet: worker.clone(),
+                preferred_token: token.clone(),
+                metadata_hash: String::from_str(&e, "ar://legacy-tx"),
+            },
+        );
+    });
+    assert_eq!(client.get_workers(&0u32, &10u32).len(), 0);
+
+    // Same preferred token: the legacy worker still lands in both indexes
+    client.update_worker(&worker, &token, &metadata(&e, "ipfs://QmFresh"));
+    let listed = client.get_workers(&0u32, &10u32);
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed.get(0).unwrap().wallet, worker);
+    assert_eq!(client.get_workers_by_token(&token, &0u32, &10u32).len(), 1);
+
+    // Updating again does not duplicate the entries
+    client.update_worker(&worker, &token, &metadata(&e, "ipfs://QmFresher"));
+    assert_eq!(client.get_worker_count(), 1);
+    assert_eq!(client.get_worker_count_by_token(&token), 1);
+}
+
+#[test]
+fn test_team_creation_and_membership() {
+    let e = Env::default();
+    e.mock_all_auths();
+    let contract_id = e.register(WorkforceRegistryContract, ());
+    let client = WorkforceRegistryContractClient::new(&e, &contract_id);
+
+    let employer = Address::generate(&e);
+    let lead = Address::generate(&e);
+    let token = Address::generate(&e);
+    register_employer(&e, &client, &employer);
+
+    let team_id = client.create_team(
+        &employer,
+        &employer,
+        &String::from_str(&e, "Engineering"),
+        &lead,
+        &token,
+        &1_000_000,
+    );
+    assert_eq!(team_id, 1);
+    assert_eq!(client.get_employer_teams(&employer), soroban_sdk::vec![&e, team_id]);
+    let team = client.get_team(&team_id).unwrap();
+    assert_eq!(team.manager, lead);
+    assert_eq!(team.monthly_cap, 1_000_000);
+
+    let w1 = Address::generate(&e);
+    let w2 = Address::generate(&e);
+    client.register_worker(&w1, &token, &metadata(&e, "ipfs://QmHash"));
+    client.register_worker(&w2, &token, &metadata(&e, "ipfs://QmHash"));
+
+    // The team manager manages membership; outsiders cannot
+    client.add_team_member(&lead, &team_id, &w1);
+    client.add_team_member(&employer, &team_id, &w2);
+    assert!(client
+        .try_add_team_member(&Address::generate(&e), &team_id, &w1)
+        .is_err());
+    assert_eq!(client.get_team_member_count(&team_id), 2);
+    assert!(client.is_team_member(&team_id, &w1));
+    assert_eq!(client.get_worker_teams(&w1), soroban_sdk::vec![&e, team_id]);
+
+    client.remove_team_member(&lead, &team_id, &w1);
+    assert!(!client.is_team_member(&team_id, &w1));
+    assert_eq!(client.get_worker_teams(&w1).len(), 0);
+    let members = client.get_team_members(&team_id, &0u32, &10u32);
+    assert_eq!(members.len(), 1);
+    assert_eq!(members.get(0).unwrap().wallet, w2);
+
+    // Only employer admins update the team itself
+    assert!(client
+        .try_update_team(&lead, &team_id, &String::from_str(&e, "Eng"), &lead, &token, &5)
+        .is_err());
+    client.update_team(&employer, &team_id, &String::from_str(&e, "Eng"), &lead, &token, &5);
+    assert_eq!(client.get_team(&team_id).unwrap().monthly_cap, 5);
+
+    // Deregistering drops the worker from its teams
+    client.deregister_worker(&w2);
+    assert_eq!(client.get_team_member_count(&team_id), 0);
+}
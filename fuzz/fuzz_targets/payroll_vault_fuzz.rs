@@ -86,6 +86,9 @@ fuzz_target!(|actions: Vec<FuzzAction>| {
             // Invariant: Treasury and Liability are non-negative
             assert!(treasury >= 0, "Treasury balance became negative");
             assert!(total_liability >= 0, "Total liability became negative");
+
+            // Invariant: Contract-level check agrees with the above
+            assert!(client.check_invariants(&token_id), "Vault invariant check failed");
         }
     }
 });